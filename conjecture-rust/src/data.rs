@@ -1,9 +1,12 @@
 // Module representing core data types that Hypothesis
 // needs.
 
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use rand::{ChaChaRng, Rng};
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
 
 pub type DataStream = Vec<u64>;
 pub type DataStreamSlice = [u64];
@@ -11,15 +14,56 @@ pub type DataStreamSlice = [u64];
 #[derive(Debug, Clone)]
 pub struct FailedDraw;
 
+// Turns a name into a label for use with DataSource::start_draw.
+// This is the 64-bit FNV-1a hash of the name, which is cheap enough
+// to compute on every draw and the same on every platform.
+pub const fn calc_label(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+// Choice sequences are stored (e.g. in the database) as the big-endian
+// bytes of each value. Any trailing partial value is ignored.
+pub fn bytes_to_u64s(bytes: &[u8]) -> DataStream {
+    let mut reader = io::Cursor::new(bytes);
+    let mut result = Vec::new();
+    while let Ok(n) = reader.read_u64::<BigEndian>() {
+        result.push(n);
+    }
+    result
+}
+
+pub fn u64s_to_bytes(ints: &DataStreamSlice) -> Vec<u8> {
+    let mut result = Vec::new();
+    for n in ints {
+        result.write_u64::<BigEndian>(*n).unwrap();
+    }
+    result
+}
+
+// The default for the maximum number of choices a single test
+// execution may make before it overflows. This keeps a runaway
+// generator from using unbounded amounts of memory.
+pub const DEFAULT_MAX_SIZE: usize = 8 * 1024;
+
 #[derive(Debug, Clone)]
 enum BitGenerator {
-    Random(ChaChaRng),
+    // Replays the prefix and then draws randomly.
+    Random(DataStream, ChaChaRng),
     Recorded(DataStream),
 }
 
 // Records information corresponding to a single draw call.
 #[derive(Debug, Clone)]
 pub struct DrawInProgress {
+    label: u64,
     depth: usize,
     start: usize,
     end: Option<usize>,
@@ -28,6 +72,9 @@ pub struct DrawInProgress {
 // Records information corresponding to a single draw call.
 #[derive(Debug, Clone)]
 pub struct Draw {
+    // Identifies the kind of thing that was drawn, e.g. the strategy
+    // that drew it, so that draws of the same kind can be matched up.
+    pub label: u64,
     pub depth: usize,
     pub start: usize,
     pub end: usize,
@@ -45,35 +92,92 @@ pub struct DataSource {
     draws: Vec<DrawInProgress>,
     draw_stack: Vec<usize>,
     written_indices: HashSet<usize>,
+    events: HashSet<String>,
+    target_observations: HashMap<String, f64>,
+    max_size: usize,
+    invalid: bool,
 }
 
 impl DataSource {
     fn new(generator: BitGenerator) -> DataSource {
         DataSource {
             bitgenerator: generator,
+            max_size: DEFAULT_MAX_SIZE,
             record: DataStream::new(),
             sizes: Vec::new(),
             draws: Vec::new(),
             draw_stack: Vec::new(),
             written_indices: HashSet::new(),
+            events: HashSet::new(),
+            target_observations: HashMap::new(),
+            invalid: false,
         }
     }
 
     pub fn from_random(random: ChaChaRng) -> DataSource {
-        DataSource::new(BitGenerator::Random(random))
+        DataSource::from_prefix_and_random(DataStream::new(), random)
+    }
+
+    pub fn from_prefix_and_random(prefix: DataStream, random: ChaChaRng) -> DataSource {
+        DataSource::new(BitGenerator::Random(prefix, random))
     }
 
     pub fn from_vec(record: DataStream) -> DataSource {
         DataSource::new(BitGenerator::Recorded(record))
     }
 
-    pub fn start_draw(&mut self) {
+    // Replays the raw input of a fuzzer such as libFuzzer, reading each
+    // choice from the next eight bytes. Unlike bytes_to_u64s this pads
+    // a trailing partial value with zeroes, so that every byte of the
+    // input can affect the test.
+    pub fn from_fuzzer_bytes(bytes: &[u8]) -> DataSource {
+        let mut padded = bytes.to_vec();
+        padded.resize(bytes.len().div_ceil(8) * 8, 0);
+        DataSource::from_vec(bytes_to_u64s(&padded))
+    }
+
+    // Limits the number of choices this source will provide. Any draw
+    // beyond that fails with FailedDraw.
+    pub fn with_max_size(mut self, max_size: usize) -> DataSource {
+        self.max_size = max_size;
+        self
+    }
+
+    // True if we can't make any more choices.
+    fn is_full(&self) -> bool {
+        let i = self.record.len();
+        i >= self.max_size
+            || match self.bitgenerator {
+                BitGenerator::Recorded(ref v) => i >= v.len(),
+                BitGenerator::Random(..) => false,
+            }
+    }
+
+    // The buffer this source is replaying, if it is not random.
+    pub fn buffer(&self) -> Option<&DataStreamSlice> {
+        match self.bitgenerator {
+            BitGenerator::Recorded(ref v) => Some(v),
+            BitGenerator::Random(..) => None,
+        }
+    }
+
+    // The choices this source will make before it starts drawing
+    // randomly, if it ever does.
+    pub fn prefix(&self) -> &DataStreamSlice {
+        match self.bitgenerator {
+            BitGenerator::Recorded(ref v) => v,
+            BitGenerator::Random(ref prefix, _) => prefix,
+        }
+    }
+
+    pub fn start_draw(&mut self, label: u64) {
         let i = self.draws.len();
         let depth = self.draw_stack.len();
         let start = self.record.len();
 
         self.draw_stack.push(i);
         self.draws.push(DrawInProgress {
+            label,
             start,
             end: None,
             depth,
@@ -88,28 +192,55 @@ impl DataSource {
         self.draws[i].end = Some(end);
     }
 
+    // Tags this test execution with a description of something that
+    // happened during it, so that the engine can report how often it
+    // happens across a run. Noting the same event twice has no effect.
+    pub fn note_event(&mut self, event: &str) {
+        if !self.events.contains(event) {
+            self.events.insert(event.to_string());
+        }
+    }
+
+    // Records a score for this test execution under the given label.
+    // When the Target phase is enabled the engine will try to find
+    // test executions that maximize each label's score. Recording a
+    // label twice replaces the earlier score. Scores that are NaN or
+    // infinite are ignored.
+    pub fn target(&mut self, label: &str, score: f64) {
+        if score.is_finite() {
+            self.target_observations.insert(label.to_string(), score);
+        }
+    }
+
+    // Marks this test execution as invalid, for when a draw finds that
+    // it cannot produce any value at all. The result will have status
+    // Invalid whatever status the test finishes with.
+    pub fn mark_invalid(&mut self) {
+        self.invalid = true;
+    }
+
     pub fn write(&mut self, value: u64) -> Result<(), FailedDraw> {
-        match self.bitgenerator {
-            BitGenerator::Recorded(ref mut v) if self.record.len() >= v.len() => Err(FailedDraw),
-            _ => {
-                self.sizes.push(0);
-                self.record.push(value);
-                Ok(())
-            }
+        if self.is_full() {
+            return Err(FailedDraw);
         }
+        self.written_indices.insert(self.record.len());
+        self.sizes.push(0);
+        self.record.push(value);
+        Ok(())
     }
 
     pub fn bits(&mut self, n_bits: u64) -> Result<u64, FailedDraw> {
+        if self.is_full() {
+            return Err(FailedDraw);
+        }
         self.sizes.push(n_bits);
         let mut result = match self.bitgenerator {
-            BitGenerator::Random(ref mut random) => random.next_u64(),
-            BitGenerator::Recorded(ref mut v) => {
-                if self.record.len() >= v.len() {
-                    return Err(FailedDraw);
-                } else {
-                    v[self.record.len()]
-                }
-            }
+            BitGenerator::Random(ref prefix, ref mut random) => match prefix.get(self.record.len())
+            {
+                Some(&v) => v,
+                None => random.next_u64(),
+            },
+            BitGenerator::Recorded(ref v) => v[self.record.len()],
         };
 
         if n_bits < 64 {
@@ -123,20 +254,34 @@ impl DataSource {
     }
 
     pub fn into_result(self, status: Status) -> TestResult {
+        let status = if self.invalid {
+            Status::Invalid
+        } else {
+            status
+        };
         TestResult {
             record: self.record,
             status,
             written_indices: self.written_indices,
+            events: self.events,
+            target_observations: self.target_observations,
+            features: HashSet::new(),
             sizes: self.sizes,
             draws: self
                 .draws
                 .into_iter()
                 .filter_map(|d| match d {
                     DrawInProgress {
+                        label,
                         depth,
                         start,
                         end: Some(end),
-                    } if start < end => Some(Draw { start, end, depth }),
+                    } if start < end => Some(Draw {
+                        label,
+                        start,
+                        end,
+                        depth,
+                    }),
                     DrawInProgress { end: None, .. } => {
                         assert!(status == Status::Invalid || status == Status::Overflow);
                         None
@@ -179,6 +324,11 @@ pub struct TestResult {
     pub draws: Vec<Draw>,
     pub sizes: Vec<u64>,
     pub written_indices: HashSet<usize>,
+    pub events: HashSet<String>,
+    pub target_observations: HashMap<String, f64>,
+    // Coverage features reported by the caller for this execution,
+    // e.g. the branches of the code under test that it reached.
+    pub features: HashSet<u64>,
 }
 
 impl Ord for TestResult {
@@ -203,3 +353,25 @@ impl PartialEq for TestResult {
 }
 
 impl Eq for TestResult {}
+
+// Decides which of two test results is simpler. The engine keeps the
+// simplest interesting example it has seen for each kind of failure,
+// and the shrinker only ever replaces an example with one that is
+// strictly simpler. The shrinker only tries examples that are smaller
+// in shortlex order, so an order that disagrees with shortlex can stop
+// shrinking early but won't make it try anything new.
+pub trait ShrinkOrder: fmt::Debug {
+    fn cmp(&self, left: &TestResult, right: &TestResult) -> Ordering;
+}
+
+// The default order, which is the same as the Ord on TestResult:
+// Shorter records are simpler, and records of the same length are
+// compared lexicographically.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Shortlex;
+
+impl ShrinkOrder for Shortlex {
+    fn cmp(&self, left: &TestResult, right: &TestResult) -> Ordering {
+        left.cmp(right)
+    }
+}
@@ -0,0 +1,327 @@
+// A prefix tree of every test execution the engine has seen,
+// modelled after DataTree in Hypothesis for Python. Given a
+// buffer we can use it to tell what the result of running the
+// test on that buffer would be without actually running it,
+// as long as the test is deterministic.
+//
+// We also use it to steer generation towards parts of the
+// search space that we have not explored yet, and to tell when
+// there is nothing left to explore.
+
+use rand::{ChaChaRng, Rng};
+
+use std::collections::{HashMap, HashSet};
+
+use crate::data::{DataStream, DataStreamSlice, Status, TestResult};
+
+#[derive(Debug, Clone)]
+enum Transition {
+    // The test drew a value with DataSource::bits(n_bits). Children
+    // are keyed by the value after masking to n_bits.
+    Draw {
+        n_bits: u64,
+        children: HashMap<u64, usize>,
+    },
+    // The test wrote a fixed value with DataSource::write, so the
+    // corresponding value in the buffer is ignored.
+    Write {
+        value: u64,
+        child: usize,
+    },
+    // The test finished here.
+    Conclusion(Box<TestResult>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    // None if we've never seen a test get this far.
+    transition: Option<Transition>,
+    // True if every possible continuation from this node has
+    // already been seen.
+    exhausted: bool,
+}
+
+// Draws of more bits than this are never going to be exhausted,
+// so we don't bother enumerating their values when looking for
+// a novel one.
+const MAX_ENUMERATED_BITS: u64 = 8;
+
+#[derive(Debug, Clone)]
+pub struct DataTree {
+    nodes: Vec<Node>,
+}
+
+pub(crate) fn mask(value: u64, n_bits: u64) -> u64 {
+    if n_bits < 64 {
+        value & ((1 << n_bits) - 1)
+    } else {
+        value
+    }
+}
+
+impl Default for DataTree {
+    fn default() -> DataTree {
+        DataTree::new()
+    }
+}
+
+impl DataTree {
+    pub fn new() -> DataTree {
+        DataTree {
+            nodes: vec![Node::default()],
+        }
+    }
+
+    fn new_node(&mut self) -> usize {
+        self.nodes.push(Node::default());
+        self.nodes.len() - 1
+    }
+
+    // Returns the node reached by taking value from node, creating it if needed.
+    fn child(&mut self, node: usize, value: u64) -> usize {
+        let existing = match self.nodes[node].transition {
+            Some(Transition::Write { child, .. }) => Some(child),
+            Some(Transition::Draw { ref children, .. }) => children.get(&value).copied(),
+            _ => None,
+        };
+        existing.unwrap_or_else(|| {
+            let child = self.new_node();
+            if let Some(Transition::Draw {
+                ref mut children, ..
+            }) = self.nodes[node].transition
+            {
+                children.insert(value, child);
+            }
+            child
+        })
+    }
+
+    fn is_exhausted_node(&self, node: usize) -> bool {
+        match self.nodes[node].transition {
+            None => false,
+            Some(Transition::Conclusion(_)) => true,
+            Some(Transition::Write { child, .. }) => self.nodes[child].exhausted,
+            Some(Transition::Draw {
+                n_bits,
+                ref children,
+            }) => {
+                n_bits < 64
+                    && children.len() as u64 == 1 << n_bits
+                    && children.values().all(|&c| self.nodes[c].exhausted)
+            }
+        }
+    }
+
+    // True if every possible test execution has been seen.
+    pub fn is_exhausted(&self) -> bool {
+        self.nodes[0].exhausted
+    }
+
+    // Returns a prefix which, when extended with arbitrary data, is
+    // guaranteed to result in a test execution we have not seen before.
+    pub fn generate_novel_prefix(&self, random: &mut ChaChaRng) -> DataStream {
+        assert!(!self.is_exhausted());
+        let mut prefix = DataStream::new();
+        let mut node = 0;
+        loop {
+            match self.nodes[node].transition {
+                None => return prefix,
+                Some(Transition::Conclusion(_)) => {
+                    panic!("BUG: Descended into an exhausted part of the tree")
+                }
+                Some(Transition::Write { value, child }) => {
+                    prefix.push(value);
+                    node = child;
+                }
+                Some(Transition::Draw {
+                    n_bits,
+                    ref children,
+                }) => {
+                    let is_open =
+                        |v: &u64| children.get(v).is_none_or(|&c| !self.nodes[c].exhausted);
+                    let value = if n_bits <= MAX_ENUMERATED_BITS {
+                        let candidates: Vec<u64> = (0..1 << n_bits).filter(is_open).collect();
+                        candidates[random.gen_range(0, candidates.len())]
+                    } else {
+                        loop {
+                            let v = mask(random.next_u64(), n_bits);
+                            if is_open(&v) {
+                                break v;
+                            }
+                        }
+                    };
+                    prefix.push(value);
+                    match children.get(&value) {
+                        Some(&child) => node = child,
+                        None => return prefix,
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the result the test would have if run on buf, if we know it.
+    pub fn lookup(&self, buf: &DataStreamSlice) -> Option<TestResult> {
+        let mut node = 0;
+        let mut record = DataStream::new();
+        let mut sizes = Vec::new();
+        let mut written_indices = HashSet::new();
+
+        loop {
+            let i = record.len();
+            let transition = self.nodes[node].transition.as_ref()?;
+            if let Transition::Conclusion(result) = transition {
+                return Some((**result).clone());
+            }
+            if i >= buf.len() {
+                // The test would try to read past the end of buf.
+                return Some(TestResult {
+                    record,
+                    status: Status::Overflow,
+                    draws: Vec::new(),
+                    sizes,
+                    written_indices,
+                    events: HashSet::new(),
+                    target_observations: HashMap::new(),
+                    features: HashSet::new(),
+                });
+            }
+            match transition {
+                Transition::Draw { n_bits, children } => {
+                    let value = mask(buf[i], *n_bits);
+                    node = *children.get(&value)?;
+                    record.push(value);
+                    sizes.push(*n_bits);
+                }
+                Transition::Write { value, child } => {
+                    written_indices.insert(i);
+                    node = *child;
+                    record.push(*value);
+                    sizes.push(0);
+                }
+                Transition::Conclusion(_) => unreachable!(),
+            }
+        }
+    }
+
+    // Record the result of a test execution. Overflows are ignored,
+    // because they say nothing about what would have happened had
+    // there been more data.
+    pub fn add(&mut self, result: &TestResult) {
+        if result.status == Status::Overflow {
+            return;
+        }
+        let mut node = 0;
+        let mut path = vec![node];
+        for (i, (&value, &n_bits)) in result.record.iter().zip(result.sizes.iter()).enumerate() {
+            let written = result.written_indices.contains(&i);
+            let consistent = match self.nodes[node].transition {
+                Some(Transition::Draw {
+                    n_bits: existing, ..
+                }) => !written && existing == n_bits,
+                Some(Transition::Write {
+                    value: existing, ..
+                }) => written && existing == value,
+                _ => false,
+            };
+            if !consistent {
+                // Either we've not been here before, or the test is not
+                // deterministic and did something different last time. In
+                // the latter case we trust the most recent run.
+                self.nodes[node].transition = Some(if written {
+                    Transition::Write {
+                        value,
+                        child: self.new_node(),
+                    }
+                } else {
+                    Transition::Draw {
+                        n_bits,
+                        children: HashMap::new(),
+                    }
+                });
+            }
+            node = self.child(node, value);
+            path.push(node);
+        }
+        self.nodes[node].transition = Some(Transition::Conclusion(Box::new(result.clone())));
+
+        for &node in path.iter().rev() {
+            self.nodes[node].exhausted = self.is_exhausted_node(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataSource;
+
+    fn run(buf: &[u64], n_bits: u64) -> TestResult {
+        let mut source = DataSource::from_vec(buf.to_vec());
+        let status = match source.bits(n_bits) {
+            Ok(n) if n > 0 => Status::Interesting(0),
+            Ok(_) => Status::Valid,
+            Err(_) => Status::Overflow,
+        };
+        source.into_result(status)
+    }
+
+    #[test]
+    fn unknown_buffers_are_not_found() {
+        let tree = DataTree::new();
+        assert!(tree.lookup(&[1]).is_none());
+    }
+
+    #[test]
+    fn finds_previous_results() {
+        let mut tree = DataTree::new();
+        tree.add(&run(&[3], 64));
+        let result = tree.lookup(&[3]).unwrap();
+        assert_eq!(result.status, Status::Interesting(0));
+        assert!(tree.lookup(&[4]).is_none());
+    }
+
+    #[test]
+    fn results_are_determined_by_prefix() {
+        let mut tree = DataTree::new();
+        tree.add(&run(&[0, 1, 2], 64));
+        let result = tree.lookup(&[0, 5]).unwrap();
+        assert_eq!(result.status, Status::Valid);
+        assert_eq!(result.record, vec![0]);
+    }
+
+    #[test]
+    fn masks_values_to_their_size() {
+        let mut tree = DataTree::new();
+        tree.add(&run(&[1], 1));
+        assert_eq!(tree.lookup(&[3]).unwrap().record, vec![1]);
+    }
+
+    #[test]
+    fn marks_small_draws_as_exhausted() {
+        let mut tree = DataTree::new();
+        tree.add(&run(&[0], 1));
+        assert!(!tree.is_exhausted());
+        tree.add(&run(&[1], 1));
+        assert!(tree.is_exhausted());
+    }
+
+    #[test]
+    fn novel_prefixes_avoid_exhausted_branches() {
+        let mut tree = DataTree::new();
+        let mut random = ChaChaRng::new_unseeded();
+        tree.add(&run(&[0], 2));
+        tree.add(&run(&[1], 2));
+        tree.add(&run(&[3], 2));
+        for _ in 0..10 {
+            assert_eq!(tree.generate_novel_prefix(&mut random), vec![2]);
+        }
+    }
+
+    #[test]
+    fn short_buffers_overflow() {
+        let mut tree = DataTree::new();
+        tree.add(&run(&[1], 1));
+        assert_eq!(tree.lookup(&[]).unwrap().status, Status::Overflow);
+    }
+}
@@ -1,25 +1,37 @@
-use crate::data::{DataSource, FailedDraw};
+use crate::data::{calc_label, DataSource, FailedDraw};
 
 use std::cmp::{Ord, Ordering, PartialOrd, Reverse};
 use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+use std::fmt;
 use std::mem;
-
-use std::u64::MAX as MAX64;
+use std::sync::OnceLock;
 
 type Draw<T> = Result<T, FailedDraw>;
 
+// Draws a boolean that is true with the given probability. The choice
+// is drawn with as few bits as the probability needs, up to 64, and is
+// true if and only if it is between 1 and some threshold, so 0 is
+// always false and 1 is always true, whichever probability it is
+// replayed with. That means that the shrinker can always turn it
+// false, and that callers can force a result by writing 0 or 1.
+// Probabilities of zero or one are forced in the same way, so that
+// they don't use up any entropy.
 pub fn weighted(source: &mut DataSource, probability: f64) -> Result<bool, FailedDraw> {
-    // TODO: Less bit-hungry implementation.
-
-    let truthy = (probability * (u64::max_value() as f64 + 1.0)).floor() as u64;
-    let probe = source.bits(64)?;
-    Ok(match (truthy, probe) {
-        (0, _) => false,
-        (MAX64, _) => true,
-        (_, 0) => false,
-        (_, 1) => true,
-        _ => probe >= MAX64 - truthy,
-    })
+    if probability <= 0.0 {
+        source.write(0)?;
+        return Ok(false);
+    }
+    if probability >= 1.0 {
+        source.write(1)?;
+        return Ok(true);
+    }
+    // Multiplying by a power of two is exact, so this only rounds
+    // probabilities too small to represent in 64 bits.
+    let truthy = ((probability * 2f64.powi(64)).round() as u64).max(1);
+    let zeros = u64::from(truthy.trailing_zeros());
+    let probe = source.bits(64 - zeros)?;
+    Ok((1..=truthy >> zeros).contains(&probe))
 }
 
 pub fn bounded_int(source: &mut DataSource, max: u64) -> Draw<u64> {
@@ -36,6 +48,243 @@ pub fn bounded_int(source: &mut DataSource, max: u64) -> Draw<u64> {
     }
 }
 
+// Floats are drawn as a sign followed by a single value in a
+// lexicographic encoding of their magnitude, chosen so that values
+// which shrink well as integers also shrink well as floats: Small
+// non-negative integers are encoded as themselves, and everything
+// else sorts after them, ordered so that lower exponents (other than
+// negative ones) and mantissas with fewer fractional bits come first.
+// Draws of floats are labelled with this so that the shrinker can
+// find them.
+pub const FLOAT_LABEL: u64 = calc_label("conjecture::distributions::floats");
+
+const MANTISSA_MASK: u64 = (1 << 52) - 1;
+const MAX_EXPONENT: u64 = 0x7ff;
+const BIAS: i64 = 1023;
+// Integral floats below this are encoded as themselves.
+const MAX_SIMPLE: u64 = 1 << 56;
+
+const NASTY_FLOAT_PROBABILITY: f64 = 0.05;
+const NASTY_FLOATS: [f64; 12] = [
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    0.5,
+    1.1,
+    f64::EPSILON,
+    f64::MIN_POSITIVE,
+    f64::MAX,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+];
+
+fn reverse_bits(x: u64, n: u64) -> u64 {
+    if n == 0 {
+        0
+    } else {
+        x.reverse_bits() >> (64 - n)
+    }
+}
+
+// Orders exponents so that 2^0, 2^1, ... come first, followed by
+// 2^-1, 2^-2, ... and then infinities and NaN.
+fn encode_exponent(exponent: u64) -> u64 {
+    if exponent == MAX_EXPONENT {
+        exponent
+    } else if exponent >= BIAS as u64 {
+        exponent - BIAS as u64
+    } else {
+        2 * BIAS as u64 - exponent
+    }
+}
+
+fn decode_exponent(encoded: u64) -> u64 {
+    if encoded == MAX_EXPONENT {
+        encoded
+    } else if encoded <= BIAS as u64 {
+        encoded + BIAS as u64
+    } else {
+        2 * BIAS as u64 - encoded
+    }
+}
+
+// Reverses the bits of the fractional part of the mantissa, so that
+// floats that are closer to being integers have smaller encodings.
+// This is its own inverse.
+fn update_mantissa(unbiased_exponent: i64, mantissa: u64) -> u64 {
+    if unbiased_exponent <= 0 {
+        reverse_bits(mantissa, 52)
+    } else if unbiased_exponent <= 51 {
+        let n_fractional = 52 - unbiased_exponent as u64;
+        let fractional = mantissa & ((1 << n_fractional) - 1);
+        (mantissa ^ fractional) | reverse_bits(fractional, n_fractional)
+    } else {
+        mantissa
+    }
+}
+
+fn is_simple(f: f64) -> bool {
+    f >= 0.0 && f.fract() == 0.0 && f < MAX_SIMPLE as f64
+}
+
+// Encodes a non-negative float so that simpler floats have smaller
+// encodings.
+pub fn float_to_lex(f: f64) -> u64 {
+    assert!(f.is_sign_positive());
+    if is_simple(f) {
+        return f as u64;
+    }
+    let bits = f.to_bits();
+    let exponent = bits >> 52;
+    let mantissa = update_mantissa(exponent as i64 - BIAS, bits & MANTISSA_MASK);
+    (1 << 63) | (encode_exponent(exponent) << 52) | mantissa
+}
+
+pub fn lex_to_float(i: u64) -> f64 {
+    if i >> 63 == 0 {
+        return (i & (MAX_SIMPLE - 1)) as f64;
+    }
+    let exponent = decode_exponent((i >> 52) & MAX_EXPONENT);
+    let mantissa = update_mantissa(exponent as i64 - BIAS, i & MANTISSA_MASK);
+    f64::from_bits((exponent << 52) | mantissa)
+}
+
+// The bounds that floats between min_value and max_value are drawn
+// from, or None if no float is allowed by them.
+pub fn float_bounds(min_value: f64, max_value: f64, allow_infinity: bool) -> Option<(f64, f64)> {
+    let (min_value, max_value) = if allow_infinity {
+        (min_value, max_value)
+    } else {
+        (min_value.max(-f64::MAX), max_value.min(f64::MAX))
+    };
+    if min_value <= max_value {
+        Some((min_value, max_value))
+    } else {
+        None
+    }
+}
+
+// Draws a float between min_value and max_value inclusive. Infinities
+// and NaN are only drawn if allowed. Values that would be out of
+// bounds are mapped into them, so when zero is out of bounds floats
+// shrink towards min_value. If no float is in bounds, the data is
+// marked invalid.
+pub fn floats(
+    source: &mut DataSource,
+    min_value: f64,
+    max_value: f64,
+    allow_nan: bool,
+    allow_infinity: bool,
+) -> Draw<f64> {
+    let Some((min_value, max_value)) = float_bounds(min_value, max_value, allow_infinity) else {
+        source.mark_invalid();
+        return Err(FailedDraw);
+    };
+    let in_range = |f: f64| (min_value..=max_value).contains(&f);
+
+    // Values that are particularly likely to cause problems are rare
+    // in the encoding, so we draw them separately some of the time.
+    let nasty: Vec<f64> = NASTY_FLOATS
+        .iter()
+        .chain(&[min_value, max_value])
+        .copied()
+        .filter(|&f| (f.is_nan() && allow_nan) || in_range(f))
+        .collect();
+    if weighted(source, NASTY_FLOAT_PROBABILITY)? {
+        let i = bounded_int(source, nasty.len() as u64 - 1)?;
+        return Ok(nasty[i as usize]);
+    }
+
+    source.start_draw(FLOAT_LABEL);
+    let negative = source.bits(1)? == 1;
+    let magnitude = lex_to_float(source.bits(64)?);
+    source.stop_draw();
+
+    let f = if negative { -magnitude } else { magnitude };
+    if (f.is_nan() && allow_nan) || in_range(f) {
+        return Ok(f);
+    }
+    // When only one sign is allowed we ignore the sign bit rather
+    // than throwing away the whole value.
+    if in_range(-f) {
+        return Ok(-f);
+    }
+    let fraction = (f.to_bits() & MANTISSA_MASK) as f64 / MANTISSA_MASK as f64;
+    let size = (max_value - min_value).min(f64::MAX);
+    Ok((min_value + size * fraction).max(min_value).min(max_value))
+}
+
+// Draws n_bits bits, using as many choices as it takes.
+fn bits_u128(source: &mut DataSource, n_bits: u64) -> Draw<u128> {
+    if n_bits <= 64 {
+        return Ok(u128::from(source.bits(n_bits)?));
+    }
+    let high = source.bits(n_bits - 64)?;
+    let low = source.bits(64)?;
+    Ok((u128::from(high) << 64) | u128::from(low))
+}
+
+// Narrower ranges than this are drawn uniformly.
+const MAX_UNIFORM_BITS: u64 = 8;
+
+// Draws an integer in 0..=max that shrinks towards 0. Unlike
+// bounded_int, values in wide ranges are not drawn uniformly: We
+// first pick how many bits the value will have, so that small values
+// are about as likely as large ones.
+pub fn bounded_u128(source: &mut DataSource, max: u128) -> Draw<u128> {
+    let bitlength = 128 - u64::from(max.leading_zeros());
+    if bitlength == 0 {
+        source.write(0)?;
+        return Ok(0);
+    }
+    let n_bits = if bitlength <= MAX_UNIFORM_BITS {
+        bitlength
+    } else {
+        bounded_int(source, bitlength)?
+    };
+    loop {
+        let probe = bits_u128(source, n_bits)?;
+        if probe <= max {
+            return Ok(probe);
+        }
+    }
+}
+
+// Draws an integer in lo..=hi that shrinks towards shrink_towards,
+// preferring values above it to values below it.
+pub fn integers_in_range_towards(
+    source: &mut DataSource,
+    lo: i128,
+    hi: i128,
+    shrink_towards: i128,
+) -> Draw<i128> {
+    assert!(lo <= hi, "Cannot draw integers from an empty range");
+    assert!(lo <= shrink_towards && shrink_towards <= hi);
+    // These can't overflow when treated as unsigned.
+    let above = hi.wrapping_sub(shrink_towards) as u128;
+    let below = shrink_towards.wrapping_sub(lo) as u128;
+    let downwards = if above == 0 || below == 0 {
+        above == 0
+    } else {
+        source.bits(1)? == 1
+    };
+    if downwards {
+        let distance = bounded_u128(source, below)?;
+        Ok(shrink_towards.wrapping_sub(distance as i128))
+    } else {
+        let distance = bounded_u128(source, above)?;
+        Ok(shrink_towards.wrapping_add(distance as i128))
+    }
+}
+
+// Draws an integer in lo..=hi that shrinks towards zero, or towards
+// whichever of lo and hi is closest to zero if zero is not in range.
+pub fn integers_in_range(source: &mut DataSource, lo: i128, hi: i128) -> Draw<i128> {
+    integers_in_range_towards(source, lo, hi, 0.max(lo).min(hi))
+}
+
 #[derive(Debug, Clone)]
 pub struct Repeat {
     min_count: u64,
@@ -86,6 +335,387 @@ impl Repeat {
     }
 }
 
+// The draw made for each branch of a recursive structure is labelled
+// with this, so that the draws of a branch's children are nested
+// inside it.
+pub const RECURSIVE_LABEL: u64 = calc_label("conjecture::distributions::recursive");
+
+// The probability of branching at the root of a recursive structure.
+// This falls off as the structure uses up its budget of nodes.
+const BRANCH_PROBABILITY: f64 = 0.5;
+
+// Decides the shape of a recursive structure, such as a tree, while it
+// is being drawn: Each node is either a leaf or a branch whose
+// children are nodes, and choosing a leaf is always the simpler
+// choice, so structures shrink towards their leaves. Nodes deeper than
+// max_depth are always leaves, and structures with more than max_nodes
+// nodes overflow. Branches get less likely as the number of nodes
+// grows, so that this rarely happens.
+#[derive(Debug, Clone)]
+pub struct Recursive {
+    max_depth: u64,
+    max_nodes: u64,
+
+    depth: u64,
+    nodes: u64,
+}
+
+impl Recursive {
+    pub fn new(max_depth: u64, max_nodes: u64) -> Recursive {
+        Recursive {
+            max_depth,
+            max_nodes,
+            depth: 0,
+            nodes: 0,
+        }
+    }
+
+    // Starts a new node, returning true if it is a branch. Every
+    // branch must be finished with stop_branch once its children have
+    // been drawn.
+    pub fn start_node(&mut self, source: &mut DataSource) -> Draw<bool> {
+        if self.nodes >= self.max_nodes {
+            return Err(FailedDraw);
+        }
+        self.nodes += 1;
+        let branch = if self.depth >= self.max_depth {
+            source.write(0)?;
+            false
+        } else {
+            let remaining = 1.0 - self.nodes as f64 / self.max_nodes as f64;
+            weighted(source, BRANCH_PROBABILITY * remaining)?
+        };
+        if branch {
+            self.depth += 1;
+            source.start_draw(RECURSIVE_LABEL);
+        }
+        Ok(branch)
+    }
+
+    pub fn stop_branch(&mut self, source: &mut DataSource) {
+        assert!(self.depth > 0);
+        self.depth -= 1;
+        source.stop_draw();
+    }
+}
+
+// Coarse categories of characters, for choosing which characters
+// text may contain. Each character is in exactly one category: the
+// first of these that it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharCategory {
+    Control,
+    Whitespace,
+    Uppercase,
+    Lowercase,
+    // Letters that have no case.
+    OtherLetter,
+    Number,
+    // We don't have tables of the Unicode punctuation categories, so
+    // this is only ASCII punctuation and symbols.
+    Punctuation,
+    Other,
+}
+
+impl CharCategory {
+    pub fn all() -> Vec<Self> {
+        vec![
+            CharCategory::Control,
+            CharCategory::Whitespace,
+            CharCategory::Uppercase,
+            CharCategory::Lowercase,
+            CharCategory::OtherLetter,
+            CharCategory::Number,
+            CharCategory::Punctuation,
+            CharCategory::Other,
+        ]
+    }
+
+    pub fn of(c: char) -> CharCategory {
+        if c.is_control() {
+            CharCategory::Control
+        } else if c.is_whitespace() {
+            CharCategory::Whitespace
+        } else if c.is_uppercase() {
+            CharCategory::Uppercase
+        } else if c.is_lowercase() {
+            CharCategory::Lowercase
+        } else if c.is_alphabetic() {
+            CharCategory::OtherLetter
+        } else if c.is_numeric() {
+            CharCategory::Number
+        } else if c.is_ascii_punctuation() {
+            CharCategory::Punctuation
+        } else {
+            CharCategory::Other
+        }
+    }
+}
+
+impl fmt::Display for CharCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CharCategory::Control => "control",
+            CharCategory::Whitespace => "whitespace",
+            CharCategory::Uppercase => "uppercase",
+            CharCategory::Lowercase => "lowercase",
+            CharCategory::OtherLetter => "other_letter",
+            CharCategory::Number => "number",
+            CharCategory::Punctuation => "punctuation",
+            CharCategory::Other => "other",
+        })
+    }
+}
+
+impl TryFrom<&str> for CharCategory {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "control" => Ok(CharCategory::Control),
+            "whitespace" => Ok(CharCategory::Whitespace),
+            "uppercase" => Ok(CharCategory::Uppercase),
+            "lowercase" => Ok(CharCategory::Lowercase),
+            "other_letter" => Ok(CharCategory::OtherLetter),
+            "number" => Ok(CharCategory::Number),
+            "punctuation" => Ok(CharCategory::Punctuation),
+            "other" => Ok(CharCategory::Other),
+            _ => Err(format!(
+                "Cannot convert to CharCategory: {} is not a valid CharCategory",
+                value
+            )),
+        }
+    }
+}
+
+// Which characters text may contain. Surrogates are never allowed,
+// because they are not valid characters on their own.
+#[derive(Debug, Clone)]
+pub struct Alphabet {
+    pub min_codepoint: u32,
+    pub max_codepoint: u32,
+    // If set, only characters in one of these categories are allowed.
+    pub categories: Option<Vec<CharCategory>>,
+    pub exclude_categories: Vec<CharCategory>,
+    pub exclude_characters: Vec<char>,
+}
+
+impl Default for Alphabet {
+    fn default() -> Alphabet {
+        Alphabet {
+            min_codepoint: 0,
+            max_codepoint: char::MAX as u32,
+            categories: None,
+            exclude_categories: Vec::new(),
+            exclude_characters: Vec::new(),
+        }
+    }
+}
+
+impl Alphabet {
+    pub fn allows(&self, c: char) -> bool {
+        (self.min_codepoint..=self.max_codepoint).contains(&(c as u32))
+            && self.allows_category(CharCategory::of(c))
+            && !self.exclude_characters.contains(&c)
+    }
+
+    fn allows_category(&self, category: CharCategory) -> bool {
+        self.categories
+            .as_ref()
+            .is_none_or(|categories| categories.contains(&category))
+            && !self.exclude_categories.contains(&category)
+    }
+
+    fn filters_categories(&self) -> bool {
+        self.categories.is_some() || !self.exclude_categories.is_empty()
+    }
+}
+
+// The number of codepoints, including surrogates.
+const N_CODEPOINTS: u32 = char::MAX as u32 + 1;
+
+// Characters are drawn as an index into the codepoints rearranged so
+// that they shrink towards '0', followed by the rest of ASCII, so
+// that simple strings look like "0" rather than "\u{0}".
+fn codepoint_for_index(i: u32) -> u32 {
+    let zero = '0' as u32;
+    if i < 128 - zero {
+        i + zero
+    } else if i < 128 {
+        i - (128 - zero)
+    } else {
+        i
+    }
+}
+
+// The surrogates, which are codepoints but not characters.
+const SURROGATES: (u32, u32) = (0xD800, 0xDFFF);
+
+// Adds a run of len indices starting at start, merging it into the
+// previous run if they are adjacent.
+fn push_run(runs: &mut Vec<(u32, u32)>, start: u32, len: u32) {
+    match runs.last_mut() {
+        Some((last_start, last_len)) if *last_start + *last_len == start => *last_len += len,
+        _ => runs.push((start, len)),
+    }
+}
+
+// Adds the indices in start..=end to runs, leaving out the ones in
+// excluded, which must be sorted.
+fn push_range_excluding(runs: &mut Vec<(u32, u32)>, start: u32, end: u32, excluded: &[u32]) {
+    let mut start = start;
+    let first = excluded.partition_point(|&x| x < start);
+    for &x in excluded[first..].iter().take_while(|&&x| x <= end) {
+        if x > start {
+            push_run(runs, start, x - start);
+        }
+        start = x + 1;
+    }
+    if start <= end {
+        push_run(runs, start, end - start + 1);
+    }
+}
+
+// Runs of consecutive characters above ASCII that are all in the same
+// category, as (first codepoint, last codepoint, category) triples.
+// Working these out looks at every character, so we only do it once.
+fn category_runs() -> &'static [(u32, u32, CharCategory)] {
+    static RUNS: OnceLock<Vec<(u32, u32, CharCategory)>> = OnceLock::new();
+    RUNS.get_or_init(|| {
+        let mut runs: Vec<(u32, u32, CharCategory)> = Vec::new();
+        for c in (128..N_CODEPOINTS).filter_map(char::from_u32) {
+            let (i, category) = (c as u32, CharCategory::of(c));
+            match runs.last_mut() {
+                Some((_, end, last)) if *end + 1 == i && *last == category => *end = i,
+                _ => runs.push((i, i, category)),
+            }
+        }
+        runs
+    })
+}
+
+// Why Characters could not be built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharactersError {
+    // No character is allowed by the alphabet.
+    EmptyAlphabet,
+}
+
+impl fmt::Display for CharactersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharactersError::EmptyAlphabet => {
+                f.write_str("Cannot draw characters from an empty alphabet")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CharactersError {}
+
+// Draws characters from an alphabet.
+#[derive(Debug, Clone)]
+pub struct Characters {
+    // The allowed characters as runs of consecutive indices, as
+    // (first index, length) pairs.
+    runs: Vec<(u32, u32)>,
+    size: u32,
+}
+
+impl Characters {
+    pub fn new(alphabet: &Alphabet) -> Result<Characters, CharactersError> {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        // Indices and codepoints only differ below 128.
+        let lo = if alphabet.min_codepoint < 128 {
+            0
+        } else {
+            alphabet.min_codepoint
+        };
+        let hi = alphabet.max_codepoint.clamp(127, N_CODEPOINTS - 1);
+        for i in lo..=hi.min(127) {
+            if char::from_u32(codepoint_for_index(i)).is_some_and(|c| alphabet.allows(c)) {
+                push_run(&mut runs, i, 1);
+            }
+        }
+        // Above ASCII we can add whole ranges of characters at once.
+        let ranges: Vec<(u32, u32)> = if alphabet.filters_categories() {
+            category_runs()
+                .iter()
+                .filter(|&&(_, _, category)| alphabet.allows_category(category))
+                .map(|&(start, end, _)| (start, end))
+                .collect()
+        } else {
+            vec![
+                (128, SURROGATES.0 - 1),
+                (SURROGATES.1 + 1, N_CODEPOINTS - 1),
+            ]
+        };
+        let mut excluded: Vec<u32> = alphabet
+            .exclude_characters
+            .iter()
+            .map(|&c| c as u32)
+            .collect();
+        excluded.sort_unstable();
+        for (start, end) in ranges {
+            let (start, end) = (start.max(lo), end.min(hi));
+            if start <= end {
+                push_range_excluding(&mut runs, start, end, &excluded);
+            }
+        }
+        let size = runs.iter().map(|&(_, len)| len).sum();
+        if size == 0 {
+            return Err(CharactersError::EmptyAlphabet);
+        }
+        Ok(Characters { runs, size })
+    }
+
+    pub fn draw(&self, source: &mut DataSource) -> Draw<char> {
+        let mut k = bounded_u128(source, u128::from(self.size - 1))? as u32;
+        for &(start, len) in &self.runs {
+            if k < len {
+                return Ok(char::from_u32(codepoint_for_index(start + k)).unwrap());
+            }
+            k -= len;
+        }
+        unreachable!()
+    }
+}
+
+// Draws of single characters in text are labelled with this, so that
+// the shrinker can treat them as siblings of each other.
+pub const CHAR_LABEL: u64 = calc_label("conjecture::distributions::text");
+
+fn expected_size(min_size: u64, max_size: u64) -> f64 {
+    (min_size as f64 + max_size.min(min_size.saturating_add(20)) as f64) * 0.5
+}
+
+// Draws a string of between min_size and max_size characters.
+pub fn text(
+    source: &mut DataSource,
+    characters: &Characters,
+    min_size: u64,
+    max_size: u64,
+) -> Draw<String> {
+    let mut repeat = Repeat::new(min_size, max_size, expected_size(min_size, max_size));
+    let mut result = String::new();
+    while repeat.should_continue(source)? {
+        source.start_draw(CHAR_LABEL);
+        result.push(characters.draw(source)?);
+        source.stop_draw();
+    }
+    Ok(result)
+}
+
+// Draws a byte string of between min_size and max_size bytes.
+pub fn binary(source: &mut DataSource, min_size: u64, max_size: u64) -> Draw<Vec<u8>> {
+    let mut repeat = Repeat::new(min_size, max_size, expected_size(min_size, max_size));
+    let mut result = Vec::new();
+    while repeat.should_continue(source)? {
+        result.push(source.bits(8)? as u8);
+    }
+    Ok(result)
+}
+
 #[derive(Debug, Clone)]
 struct SamplerEntry {
     primary: usize,
@@ -125,32 +755,64 @@ impl PartialEq for SamplerEntry {
 
 impl Eq for SamplerEntry {}
 
+// Why a Sampler could not be built.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplerError {
+    // The weight at this index is negative, infinite or NaN.
+    InvalidWeight(usize, f32),
+}
+
+impl fmt::Display for SamplerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamplerError::InvalidWeight(i, w) => write!(
+                f,
+                "Invalid weight {} at index {}: Weights must be finite and non-negative",
+                w, i
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SamplerError {}
+
+// Draws indices into a list of weights, with probability proportional
+// to their weight. Indices with zero weight are never drawn. If every
+// weight is zero there is nothing to draw, and sampling marks the data
+// as invalid.
 #[derive(Debug, Clone)]
 pub struct Sampler {
     table: Vec<SamplerEntry>,
 }
 
 impl Sampler {
-    pub fn new(weights: &[f32]) -> Sampler {
-        // FIXME: The correct thing to do here is to allow this,
-        // return early, and make this reject the data, but we don't
-        // currently have the status built into our data properly...
-        assert!(!weights.is_empty());
+    pub fn new(weights: &[f32]) -> Result<Sampler, SamplerError> {
+        if let Some((i, &w)) = weights
+            .iter()
+            .enumerate()
+            .find(|&(_, &w)| !(w.is_finite() && w >= 0.0))
+        {
+            return Err(SamplerError::InvalidWeight(i, w));
+        }
 
         let mut table = Vec::new();
 
         let mut small = BinaryHeap::new();
         let mut large = BinaryHeap::new();
 
-        let total: f32 = weights.iter().sum();
+        // Summing as f64 means that large weights can't overflow.
+        let total: f64 = weights.iter().map(|&w| f64::from(w)).sum();
 
         let mut scaled_probabilities = Vec::new();
 
-        let n = weights.len() as f32;
+        let n = weights.iter().filter(|&&w| w > 0.0).count() as f64;
 
-        for (i, w) in weights.iter().enumerate() {
-            let scaled = n * w / total;
+        for (i, &w) in weights.iter().enumerate() {
+            let scaled = (n * f64::from(w) / total) as f32;
             scaled_probabilities.push(scaled);
+            if w <= 0.0 {
+                continue;
+            }
             if (scaled - 1.0).abs() < f32::EPSILON {
                 table.push(SamplerEntry::single(i))
             } else if scaled > 1.0 {
@@ -198,12 +860,19 @@ impl Sampler {
         }
 
         table.sort();
-        assert!(!table.is_empty());
-        Sampler { table }
+        Ok(Sampler { table })
+    }
+
+    // True if there is nothing to sample, because every weight is zero.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
     }
 
     pub fn sample(&self, source: &mut DataSource) -> Draw<usize> {
-        assert!(!self.table.is_empty());
+        if self.is_empty() {
+            source.mark_invalid();
+            return Err(FailedDraw);
+        }
         let i = bounded_int(source, self.table.len() as u64 - 1)? as usize;
         let entry = &self.table[i];
         let use_alternate = weighted(source, entry.use_alternate as f64)?;
@@ -227,7 +896,7 @@ pub fn good_bitlengths() -> Sampler {
         0.1, 0.1, 0.1, 0.1, 0.1, 0.1, 0.1, // 8 bytes (last bit spare for sign)
     ];
     assert!(weights.len() == 63);
-    Sampler::new(&weights)
+    Sampler::new(&weights).unwrap()
 }
 
 pub fn integer_from_bitlengths(source: &mut DataSource, bitlengths: &Sampler) -> Draw<i64> {
@@ -240,3 +909,277 @@ pub fn integer_from_bitlengths(source: &mut DataSource, bitlengths: &Sampler) ->
         Ok(base)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{ChaChaRng, Rng};
+
+    #[test]
+    fn sampler_never_draws_zero_weights() {
+        let sampler = Sampler::new(&[0.0, 1.0, 0.0, 3.0, 0.0]).unwrap();
+        let mut source = DataSource::from_random(ChaChaRng::new_unseeded());
+        for _ in 0..1000 {
+            let i = sampler.sample(&mut source).unwrap();
+            assert!(i == 1 || i == 3);
+        }
+    }
+
+    #[test]
+    fn empty_sampler_marks_data_invalid() {
+        for weights in &[vec![], vec![0.0, 0.0]] {
+            let sampler = Sampler::new(weights).unwrap();
+            assert!(sampler.is_empty());
+            let mut source = DataSource::from_random(ChaChaRng::new_unseeded());
+            assert!(sampler.sample(&mut source).is_err());
+            let result = source.into_result(crate::data::Status::Overflow);
+            assert_eq!(result.status, crate::data::Status::Invalid);
+        }
+    }
+
+    #[test]
+    fn sampler_rejects_invalid_weights() {
+        assert_eq!(
+            Sampler::new(&[1.0, -1.0]).unwrap_err(),
+            SamplerError::InvalidWeight(1, -1.0)
+        );
+        assert!(Sampler::new(&[f32::NAN]).is_err());
+        assert!(Sampler::new(&[1.0, f32::INFINITY]).is_err());
+    }
+
+    #[test]
+    fn recursive_nodes_are_leaves_at_max_depth() {
+        let mut source = DataSource::from_vec(vec![1, 1, 0, 0]);
+        let mut recursive = Recursive::new(2, 100);
+        assert!(recursive.start_node(&mut source).unwrap());
+        assert!(recursive.start_node(&mut source).unwrap());
+        assert!(!recursive.start_node(&mut source).unwrap());
+        recursive.stop_branch(&mut source);
+        assert!(!recursive.start_node(&mut source).unwrap());
+        recursive.stop_branch(&mut source);
+        let result = source.into_result(crate::data::Status::Valid);
+        assert_eq!(result.record, vec![1, 1, 0, 0]);
+        let depths: Vec<usize> = result
+            .draws
+            .iter()
+            .filter(|d| d.label == RECURSIVE_LABEL)
+            .map(|d| d.depth)
+            .collect();
+        assert_eq!(depths, vec![0, 1]);
+    }
+
+    #[test]
+    fn recursive_overflows_past_max_nodes() {
+        let mut source = DataSource::from_random(ChaChaRng::new_unseeded());
+        let mut recursive = Recursive::new(1, 3);
+        assert!(recursive.start_node(&mut source).is_ok());
+        assert!(recursive.start_node(&mut source).is_ok());
+        assert!(recursive.start_node(&mut source).is_ok());
+        assert!(recursive.start_node(&mut source).is_err());
+    }
+
+    #[test]
+    fn weighted_uses_few_bits() {
+        let mut source = DataSource::from_random(ChaChaRng::new_unseeded());
+        for &p in &[0.5, 0.25, 0.75, 0.1] {
+            weighted(&mut source, p).unwrap();
+        }
+        weighted(&mut source, 0.0).unwrap();
+        weighted(&mut source, 1.0).unwrap();
+        let record = source.into_result(crate::data::Status::Valid).record;
+        assert!(record[0] < 2);
+        assert!(record[1] < 4);
+        assert!(record[2] < 4);
+        assert!(record[3] < 1 << 60);
+        assert_eq!(&record[4..], &[0, 1]);
+    }
+
+    #[test]
+    fn weighted_zero_is_false_and_one_is_true() {
+        for &p in &[0.001, 0.1, 0.5, 0.9, 0.999] {
+            assert!(!weighted(&mut DataSource::from_vec(vec![0]), p).unwrap());
+            assert!(weighted(&mut DataSource::from_vec(vec![1]), p).unwrap());
+        }
+    }
+
+    #[test]
+    fn weighted_respects_probability() {
+        let mut source = DataSource::from_random(ChaChaRng::new_unseeded()).with_max_size(10000);
+        let n = (0..10000)
+            .filter(|_| weighted(&mut source, 0.1).unwrap())
+            .count();
+        assert!((800..1200).contains(&n));
+    }
+
+    #[test]
+    fn weighted_keeps_small_probabilities() {
+        let mut source = DataSource::from_random(ChaChaRng::new_unseeded()).with_max_size(100000);
+        let n = (0..100000)
+            .filter(|_| weighted(&mut source, 0.0001).unwrap())
+            .count();
+        assert!((1..30).contains(&n), "{}", n);
+    }
+
+    #[test]
+    fn sampler_draws_rare_indices_rarely() {
+        let sampler = Sampler::new(&[10000.0, 1.0]).unwrap();
+        let mut source = DataSource::from_random(ChaChaRng::new_unseeded()).with_max_size(500000);
+        let n = (0..100000)
+            .filter(|_| sampler.sample(&mut source).unwrap() == 1)
+            .count();
+        // We expect about 10 of these.
+        assert!((1..30).contains(&n), "{}", n);
+    }
+
+    #[test]
+    fn float_encoding_round_trips() {
+        for &f in &[0.0, 1.0, 2.5, 0.1, 1e-300, 1e300, f64::MAX, f64::INFINITY] {
+            assert_eq!(lex_to_float(float_to_lex(f)), f);
+        }
+        assert!(lex_to_float(float_to_lex(f64::NAN)).is_nan());
+    }
+
+    #[test]
+    fn simpler_floats_have_smaller_encodings() {
+        let simplest_first = [0.0, 1.0, 3.0, 1.5, 1e20, 0.5, 0.25, 0.1, f64::INFINITY];
+        for pair in simplest_first.windows(2) {
+            assert!(float_to_lex(pair[0]) < float_to_lex(pair[1]), "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn integers_in_range_cover_wide_ranges() {
+        let mut source = DataSource::from_vec(vec![0, 100, 1 << 35, 0]);
+        assert_eq!(
+            integers_in_range(&mut source, i128::MIN, i128::MAX).unwrap(),
+            1 << 99
+        );
+        let mut source = DataSource::from_vec(vec![1, 4, 3]);
+        assert_eq!(integers_in_range(&mut source, -1000, 1000).unwrap(), -3);
+    }
+
+    #[test]
+    fn integers_in_range_are_simplest_at_the_bound_closest_to_zero() {
+        let mut source = DataSource::from_vec(vec![0, 0]);
+        assert_eq!(integers_in_range(&mut source, -1000, -10).unwrap(), -10);
+        let mut source = DataSource::from_vec(vec![0, 0]);
+        assert_eq!(integers_in_range(&mut source, 10, 1000).unwrap(), 10);
+    }
+
+    #[test]
+    fn characters_shrink_towards_zero_then_ascii() {
+        let characters = Characters::new(&Alphabet::default()).unwrap();
+        let mut source = DataSource::from_vec(vec![0, 0, 1, 1, 6, 42]);
+        assert_eq!(characters.draw(&mut source).unwrap(), '0');
+        assert_eq!(characters.draw(&mut source).unwrap(), '1');
+        assert_eq!(characters.draw(&mut source).unwrap(), 'Z');
+    }
+
+    #[test]
+    fn characters_respect_the_alphabet() {
+        let alphabet = Alphabet {
+            categories: Some(vec![CharCategory::Lowercase, CharCategory::Number]),
+            exclude_characters: vec!['0'],
+            max_codepoint: 127,
+            ..Alphabet::default()
+        };
+        let characters = Characters::new(&alphabet).unwrap();
+        let mut random = ChaChaRng::new_unseeded();
+        for _ in 0..100 {
+            let mut source = DataSource::from_random(random.gen());
+            let c = characters.draw(&mut source).unwrap();
+            assert!(
+                c.is_ascii_lowercase() || ('1'..='9').contains(&c),
+                "{:?}",
+                c
+            );
+        }
+    }
+
+    #[test]
+    fn characters_without_categories_allow_every_category() {
+        let alphabet = Alphabet {
+            min_codepoint: 100,
+            max_codepoint: 0xE005,
+            exclude_characters: vec!['a', '\u{D7FF}', '\u{E000}', '\u{E005}'],
+            ..Alphabet::default()
+        };
+        // Allowing every category gives the same characters, but
+        // goes through the table of categories.
+        let by_category = Characters::new(&Alphabet {
+            categories: Some(CharCategory::all()),
+            ..alphabet.clone()
+        })
+        .unwrap();
+        let characters = Characters::new(&alphabet).unwrap();
+        assert_eq!(characters.runs, by_category.runs);
+        assert_eq!(characters.size, by_category.size);
+    }
+
+    #[test]
+    fn characters_by_category_match_the_alphabet() {
+        let alphabet = Alphabet {
+            min_codepoint: 40,
+            max_codepoint: 0xE100,
+            categories: Some(vec![CharCategory::Uppercase, CharCategory::Other]),
+            exclude_characters: vec!['A', '\u{C0}', '\u{E000}'],
+            ..Alphabet::default()
+        };
+        let mut expected: Vec<(u32, u32)> = Vec::new();
+        for i in 0..N_CODEPOINTS {
+            if char::from_u32(codepoint_for_index(i)).is_some_and(|c| alphabet.allows(c)) {
+                push_run(&mut expected, i, 1);
+            }
+        }
+        assert_eq!(Characters::new(&alphabet).unwrap().runs, expected);
+    }
+
+    #[test]
+    fn characters_reject_empty_alphabets() {
+        let alphabets = [
+            Alphabet {
+                categories: Some(Vec::new()),
+                ..Alphabet::default()
+            },
+            Alphabet {
+                min_codepoint: N_CODEPOINTS,
+                ..Alphabet::default()
+            },
+            Alphabet {
+                min_codepoint: 0xD800,
+                max_codepoint: 0xDFFF,
+                ..Alphabet::default()
+            },
+        ];
+        for alphabet in &alphabets {
+            assert_eq!(
+                Characters::new(alphabet).unwrap_err(),
+                CharactersError::EmptyAlphabet
+            );
+        }
+    }
+
+    #[test]
+    fn floats_respect_bounds() {
+        let mut source =
+            DataSource::from_vec(vec![0, 1, float_to_lex(1024.0), 0, 0, f64::NAN.to_bits()]);
+        assert_eq!(floats(&mut source, 1.0, 2.0, false, false).unwrap(), 1.0);
+        let f = floats(&mut source, -1.0, 1.0, false, false).unwrap();
+        assert!((-1.0..=1.0).contains(&f));
+    }
+
+    #[test]
+    fn floats_from_empty_ranges_are_invalid() {
+        for &(lo, hi, allow_infinity) in &[
+            (1.0, 0.0, true),
+            (f64::NAN, 0.0, true),
+            (f64::INFINITY, f64::INFINITY, false),
+        ] {
+            assert_eq!(float_bounds(lo, hi, allow_infinity), None);
+            let mut source = DataSource::from_vec(vec![0, 0, 0]);
+            assert!(floats(&mut source, lo, hi, false, allow_infinity).is_err());
+            let result = source.into_result(crate::data::Status::Valid);
+            assert_eq!(result.status, crate::data::Status::Invalid);
+        }
+    }
+}
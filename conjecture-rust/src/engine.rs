@@ -1,29 +1,72 @@
 // Core module that provides a main execution loop and
 // the API that can be used to get test data from it.
 
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use rand::{ChaChaRng, Rng, SeedableRng};
 
-use std::cmp::Reverse;
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
-use std::io;
+use std::fmt;
+use std::future::Future;
 use std::mem;
-use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
-use std::thread;
-
-use crate::data::{DataSource, DataStreamSlice, Status, TestResult};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::data::{
+    bytes_to_u64s, u64s_to_bytes, DataSource, DataStream, DataStreamSlice, Shortlex, ShrinkOrder,
+    Status, TestResult, DEFAULT_MAX_SIZE,
+};
 use crate::database::BoxedDatabase;
+use crate::datatree::{self, DataTree};
+use crate::distributions::{float_to_lex, lex_to_float, FLOAT_LABEL};
 use crate::intminimize::minimize_integer;
+use crate::mutator;
+use crate::statistics::Statistics;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Phase {
+    // Run the examples given in Settings::explicit_examples.
+    Explicit,
+    // Replay failing examples saved in the database by previous runs.
+    Reuse,
+    // Randomly generate new examples.
+    Generate,
+    // Try to find examples that maximize the scores recorded with
+    // DataSource::target by mutating the best examples seen so far.
+    Target,
+    // Explore the neighbourhood of the failing and best targeted
+    // examples found so far by changing individual draws in them.
+    Mutate,
+    // Shrink any failing examples found by the other phases.
     Shrink,
 }
 
 impl Phase {
     pub fn all() -> Vec<Self> {
-        vec![Phase::Shrink]
+        vec![
+            Phase::Explicit,
+            Phase::Reuse,
+            Phase::Generate,
+            Phase::Target,
+            Phase::Mutate,
+            Phase::Shrink,
+        ]
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Explicit => "explicit",
+            Phase::Reuse => "reuse",
+            Phase::Generate => "generate",
+            Phase::Target => "target",
+            Phase::Mutate => "mutate",
+            Phase::Shrink => "shrink",
+        })
     }
 }
 
@@ -32,6 +75,11 @@ impl TryFrom<&str> for Phase {
 
     fn try_from(value: &str) -> Result<Self, String> {
         match value {
+            "explicit" => Ok(Phase::Explicit),
+            "reuse" => Ok(Phase::Reuse),
+            "generate" => Ok(Phase::Generate),
+            "target" => Ok(Phase::Target),
+            "mutate" => Ok(Phase::Mutate),
             "shrink" => Ok(Phase::Shrink),
             _ => Err(format!(
                 "Cannot convert to Phase: {} is not a valid Phase",
@@ -41,28 +89,312 @@ impl TryFrom<&str> for Phase {
     }
 }
 
+// Problems with the way test data is being generated that make
+// it unlikely that a run will do anything useful. We check for
+// these while generating the first few examples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HealthCheck {
+    // Too many examples were rejected as invalid.
+    FilterTooMuch,
+    // Too many examples tried to use more data than was available.
+    TooManyOverflows,
+    // The examples being generated are very large.
+    DataTooLarge,
+    // The examples are taking a long time to run.
+    TooSlow,
+}
+
+impl HealthCheck {
+    pub fn all() -> Vec<Self> {
+        vec![
+            HealthCheck::FilterTooMuch,
+            HealthCheck::TooManyOverflows,
+            HealthCheck::DataTooLarge,
+            HealthCheck::TooSlow,
+        ]
+    }
+}
+
+impl TryFrom<&str> for HealthCheck {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "filter_too_much" => Ok(HealthCheck::FilterTooMuch),
+            "too_many_overflows" => Ok(HealthCheck::TooManyOverflows),
+            "data_too_large" => Ok(HealthCheck::DataTooLarge),
+            "too_slow" => Ok(HealthCheck::TooSlow),
+            _ => Err(format!(
+                "Cannot convert to HealthCheck: {} is not a valid HealthCheck",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for HealthCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            HealthCheck::FilterTooMuch => {
+                "It looks like the test is filtering out too many examples: \
+                 Only a small fraction of the examples tried were valid."
+            }
+            HealthCheck::TooManyOverflows => {
+                "Examples routinely ran out of data while being generated, \
+                 which usually means the data being generated is too large."
+            }
+            HealthCheck::DataTooLarge => {
+                "The examples being generated are very large, which will \
+                 make testing slow and failures hard to shrink."
+            }
+            HealthCheck::TooSlow => {
+                "Generating and running the first examples was very slow, \
+                 which will make testing take a long time."
+            }
+        };
+        f.write_str(message)
+    }
+}
+
+// The individual passes the shrinker can run. Callers can choose
+// which of these to use with Settings::shrink_passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShrinkPass {
+    AdaptiveDelete,
+    MinimizeIndividualBlocks,
+    MinimizeDuplicatedBlocks,
+    ReorderBlocks,
+    LowerAndDelete,
+    DeleteAllRanges,
+    SortSiblingDraws,
+    MinimizeDrawPairs,
+    MinimizeFloats,
+}
+
+impl ShrinkPass {
+    pub fn all() -> Vec<Self> {
+        vec![
+            ShrinkPass::AdaptiveDelete,
+            ShrinkPass::MinimizeIndividualBlocks,
+            ShrinkPass::MinimizeDuplicatedBlocks,
+            ShrinkPass::ReorderBlocks,
+            ShrinkPass::LowerAndDelete,
+            ShrinkPass::DeleteAllRanges,
+            ShrinkPass::SortSiblingDraws,
+            ShrinkPass::MinimizeDrawPairs,
+            ShrinkPass::MinimizeFloats,
+        ]
+    }
+
+    // Expensive passes are only run once the cheap ones have stopped
+    // making progress.
+    pub fn is_expensive(self) -> bool {
+        match self {
+            ShrinkPass::AdaptiveDelete
+            | ShrinkPass::MinimizeIndividualBlocks
+            | ShrinkPass::MinimizeDuplicatedBlocks
+            | ShrinkPass::SortSiblingDraws
+            | ShrinkPass::MinimizeFloats => false,
+            ShrinkPass::ReorderBlocks
+            | ShrinkPass::LowerAndDelete
+            | ShrinkPass::DeleteAllRanges
+            | ShrinkPass::MinimizeDrawPairs => true,
+        }
+    }
+}
+
+impl fmt::Display for ShrinkPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ShrinkPass::AdaptiveDelete => "adaptive_delete",
+            ShrinkPass::MinimizeIndividualBlocks => "minimize_individual_blocks",
+            ShrinkPass::MinimizeDuplicatedBlocks => "minimize_duplicated_blocks",
+            ShrinkPass::ReorderBlocks => "reorder_blocks",
+            ShrinkPass::LowerAndDelete => "lower_and_delete",
+            ShrinkPass::DeleteAllRanges => "delete_all_ranges",
+            ShrinkPass::SortSiblingDraws => "sort_sibling_draws",
+            ShrinkPass::MinimizeDrawPairs => "minimize_draw_pairs",
+            ShrinkPass::MinimizeFloats => "minimize_floats",
+        })
+    }
+}
+
+impl TryFrom<&str> for ShrinkPass {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "adaptive_delete" => Ok(ShrinkPass::AdaptiveDelete),
+            "minimize_individual_blocks" => Ok(ShrinkPass::MinimizeIndividualBlocks),
+            "minimize_duplicated_blocks" => Ok(ShrinkPass::MinimizeDuplicatedBlocks),
+            "reorder_blocks" => Ok(ShrinkPass::ReorderBlocks),
+            "lower_and_delete" => Ok(ShrinkPass::LowerAndDelete),
+            "delete_all_ranges" => Ok(ShrinkPass::DeleteAllRanges),
+            "sort_sibling_draws" => Ok(ShrinkPass::SortSiblingDraws),
+            "minimize_draw_pairs" => Ok(ShrinkPass::MinimizeDrawPairs),
+            "minimize_floats" => Ok(ShrinkPass::MinimizeFloats),
+            _ => Err(format!(
+                "Cannot convert to ShrinkPass: {} is not a valid ShrinkPass",
+                value
+            )),
+        }
+    }
+}
+
+// We only run health checks until we have seen this many valid
+// examples. If things look fine by then they probably are.
+const HEALTH_CHECK_VALID_EXAMPLES: u64 = 10;
+const HEALTH_CHECK_MAX_INVALID: u64 = 50;
+const HEALTH_CHECK_MAX_OVERFLOWS: u64 = 20;
+// The total number of choices made across every health checked example.
+const HEALTH_CHECK_MAX_CHOICES: usize = 10 * 1024;
+const HEALTH_CHECK_MAX_DURATION: Duration = Duration::from_secs(1);
+
+// What we have seen so far of the examples being health checked.
+#[derive(Debug, Default)]
+struct HealthCheckState {
+    valid: u64,
+    invalid: u64,
+    overflows: u64,
+    choices: usize,
+    elapsed: Duration,
+}
+
+impl HealthCheckState {
+    fn record(&mut self, result: &TestResult, elapsed: Duration) {
+        match result.status {
+            Status::Valid => self.valid += 1,
+            Status::Invalid => self.invalid += 1,
+            Status::Overflow => self.overflows += 1,
+            Status::Interesting(_) => (),
+        }
+        self.choices += result.record.len();
+        self.elapsed += elapsed;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.valid >= HEALTH_CHECK_VALID_EXAMPLES
+    }
+
+    fn failures(&self) -> Vec<HealthCheck> {
+        let mut failures = Vec::new();
+        if self.invalid >= HEALTH_CHECK_MAX_INVALID {
+            failures.push(HealthCheck::FilterTooMuch);
+        }
+        if self.overflows >= HEALTH_CHECK_MAX_OVERFLOWS {
+            failures.push(HealthCheck::TooManyOverflows);
+        }
+        if self.choices >= HEALTH_CHECK_MAX_CHOICES {
+            failures.push(HealthCheck::DataTooLarge);
+        }
+        if self.elapsed >= HEALTH_CHECK_MAX_DURATION {
+            failures.push(HealthCheck::TooSlow);
+        }
+        failures
+    }
+}
+
+// Configuration for a single run of the engine.
 #[derive(Debug, Clone)]
-enum LoopExitReason {
+pub struct Settings {
+    // The number of valid examples to generate before stopping.
+    pub max_examples: u64,
+    // The maximum number of choices a single test execution can make.
+    // Any test that tries to make more is stopped with Status::Overflow.
+    pub max_choices: usize,
+    pub phases: Vec<Phase>,
+    // Choice sequences to try before anything else, e.g. ones that
+    // are known to have found bugs in the past.
+    pub explicit_examples: Vec<DataStream>,
+    // Limits on how much work we are prepared to do shrinking. Once
+    // any of these is hit we stop and report the best examples found
+    // so far, with LoopExitReason::ShrinkLimitReached.
+    pub max_shrinks: Option<u64>,
+    pub max_shrink_time_per_label: Option<Duration>,
+    pub max_total_shrink_time: Option<Duration>,
+    // Health checks that should not cause the run to fail.
+    pub suppress_health_check: Vec<HealthCheck>,
+    // The shrink passes to use. Passes that are left out are never run.
+    pub shrink_passes: Vec<ShrinkPass>,
+    // Which examples count as simpler, both when shrinking and when
+    // deciding which examples to report.
+    pub shrink_order: Rc<dyn ShrinkOrder>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            max_examples: 100,
+            max_choices: DEFAULT_MAX_SIZE,
+            phases: Phase::all(),
+            explicit_examples: Vec::new(),
+            max_shrinks: None,
+            max_shrink_time_per_label: None,
+            max_total_shrink_time: Some(Duration::from_secs(300)),
+            suppress_health_check: Vec::new(),
+            shrink_passes: ShrinkPass::all(),
+            shrink_order: Rc::new(Shortlex),
+        }
+    }
+}
+
+// Why the main loop stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoopExitReason {
     Complete,
     MaxExamples,
-    Shutdown,
+    // We have seen every possible test execution.
+    Exhausted,
+    // Shrinking stopped early because it hit one of the limits in
+    // Settings, so the examples found may not be fully shrunk.
+    ShrinkLimitReached,
+    // Generation was stopped early because the test failed a health
+    // check that was not suppressed in Settings.
+    HealthCheckFailed(HealthCheck),
 }
 
-#[derive(Debug)]
-enum LoopCommand {
-    RunThis(DataSource),
-    Finished(LoopExitReason, MainGenerationLoop),
+// The main loop and the engine run on the same thread: The main
+// loop is an async state machine which the engine polls whenever
+// it is asked for a new data source. When the loop wants a test
+// executed it leaves the data source here and suspends, and the
+// engine leaves the corresponding result here before resuming it.
+#[derive(Debug, Default)]
+struct Exchange {
+    request: Option<DataSource>,
+    response: Option<TestResult>,
+}
+
+type SharedExchange = Rc<RefCell<Exchange>>;
+
+// Future that completes once the engine has supplied the result
+// of the most recently requested test execution.
+struct AwaitResult {
+    exchange: SharedExchange,
+}
+
+impl Future for AwaitResult {
+    type Output = TestResult;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<TestResult> {
+        match self.exchange.borrow_mut().response.take() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
 }
 
 #[derive(Debug)]
 struct MainGenerationLoop {
     name: String,
     database: BoxedDatabase,
-    receiver: Receiver<TestResult>,
-    sender: SyncSender<LoopCommand>,
-    max_examples: u64,
+    exchange: SharedExchange,
+    settings: Settings,
     random: ChaChaRng,
-    phases: Vec<Phase>,
+
+    // Every result we have seen so far, so that we never need to
+    // run the test on the same data twice, and so that generation
+    // can seek out data we haven't tried.
+    tree: DataTree,
 
     best_example: Option<TestResult>,
     minimized_examples: HashMap<u64, TestResult>,
@@ -70,31 +402,65 @@ struct MainGenerationLoop {
 
     valid_examples: u64,
     invalid_examples: u64,
+    overflow_examples: u64,
     interesting_examples: u64,
+
+    // None once we are done with health checks.
+    health_check: Option<HealthCheckState>,
+
+    // When the caller reports coverage features, every example that
+    // reached a feature we had not seen before, so that generation can
+    // explore around them.
+    corpus: Vec<DataStream>,
+    seen_features: HashSet<u64>,
+
+    // For each target label, the highest score seen and the example
+    // that achieved it.
+    best_targets: HashMap<String, (f64, TestResult)>,
+
+    statistics: Statistics,
+    // The phase we are currently in and when it started.
+    current_phase: Option<(Phase, Instant)>,
+
+    // The number of times we have actually run the test, and the
+    // number of those that happened before we started shrinking
+    // and targeting.
+    calls: u64,
+    calls_before_shrinking: u64,
+    calls_before_targeting: u64,
+    shrinking_started: Option<Instant>,
+    // Whether we gave up on shrinking some label early.
+    shrinking_cut_short: bool,
 }
 
 type StepResult = Result<(), LoopExitReason>;
 
 impl MainGenerationLoop {
-    fn run(mut self) {
-        let result = self.loop_body();
+    async fn run(mut self) -> (LoopExitReason, MainGenerationLoop) {
+        let result = self.loop_body().await;
+        self.finish_phase();
         match result {
-            // Silent shutdown when the main thread terminates
-            Err(LoopExitReason::Shutdown) => (),
-            Err(reason) => {
-                // Must clone because otherwise it is borrowed.
-                let shutdown_sender = self.sender.clone();
-                shutdown_sender
-                    .send(LoopCommand::Finished(reason, self))
-                    .unwrap()
-            }
+            Err(reason) => (reason, self),
             Ok(_) => panic!("BUG: Generation loop was not supposed to return normally."),
         }
     }
 
-    fn run_previous_examples(&mut self) -> Result<(), LoopExitReason> {
+    fn start_phase(&mut self, phase: Phase) {
+        self.finish_phase();
+        self.current_phase = Some((phase, Instant::now()));
+    }
+
+    fn finish_phase(&mut self) {
+        if let Some((phase, start)) = self.current_phase.take() {
+            self.statistics.phases.entry(phase).or_default().duration += start.elapsed();
+        }
+    }
+
+    async fn run_previous_examples(&mut self) -> Result<(), LoopExitReason> {
         for v in self.database.fetch(&self.name) {
-            let result = self.execute(DataSource::from_vec(bytes_to_u64s(&v)))?;
+            let result = self
+                .execute(DataSource::from_vec(bytes_to_u64s(&v)))
+                .await?;
             let should_delete = match &result.status {
                 Status::Interesting(_) => u64s_to_bytes(&result.record) != v,
                 _ => true,
@@ -107,14 +473,44 @@ impl MainGenerationLoop {
         Ok(())
     }
 
-    fn loop_body(&mut self) -> StepResult {
-        self.run_previous_examples()?;
+    async fn run_explicit_examples(&mut self) -> StepResult {
+        for record in self.settings.explicit_examples.clone() {
+            self.execute(DataSource::from_vec(record)).await?;
+        }
+        Ok(())
+    }
+
+    async fn loop_body(&mut self) -> StepResult {
+        if self.settings.phases.contains(&Phase::Explicit) {
+            self.start_phase(Phase::Explicit);
+            self.run_explicit_examples().await?;
+        }
+
+        if self.settings.phases.contains(&Phase::Reuse) {
+            self.start_phase(Phase::Reuse);
+            self.run_previous_examples().await?;
+        }
 
-        if self.interesting_examples == 0 {
-            self.generate_examples()?;
+        if self.interesting_examples == 0 && self.settings.phases.contains(&Phase::Generate) {
+            self.start_phase(Phase::Generate);
+            self.generate_examples().await?;
         }
 
-        if !self.phases.contains(&Phase::Shrink) {
+        if self.interesting_examples == 0 && self.settings.phases.contains(&Phase::Target) {
+            self.start_phase(Phase::Target);
+            self.optimise_targets().await?;
+        }
+
+        if self.settings.phases.contains(&Phase::Mutate) {
+            self.start_phase(Phase::Mutate);
+            self.mutate_examples().await?;
+        }
+
+        if self.interesting_examples == 0 && self.settings.phases.contains(&Phase::Generate) {
+            return Err(LoopExitReason::MaxExamples);
+        }
+
+        if !self.settings.phases.contains(&Phase::Shrink) {
             return Err(LoopExitReason::Complete);
         }
         // At the start of this loop we usually only have one example in
@@ -133,46 +529,260 @@ impl MainGenerationLoop {
         //
         // In principle this might cause us to loop for a very long time before
         // eventually settling on a fixed point, but when that happens we
-        // should hit limits on shrinking (which we haven't implemented yet).
+        // should hit the limits on shrinking in self.settings.
+        self.start_phase(Phase::Shrink);
+        self.shrinking_started = Some(Instant::now());
+        self.calls_before_shrinking = self.calls;
         while self.minimized_examples.len() > self.fully_minimized.len() {
             let keys: Vec<u64> = self.minimized_examples.keys().copied().collect();
-            for label in &keys {
-                if self.fully_minimized.insert(*label) {
-                    let target = self.minimized_examples[label].clone();
-                    let mut shrinker =
-                        Shrinker::new(self, target, |r| r.status == Status::Interesting(*label));
-
-                    shrinker.run()?;
+            for label in keys {
+                if self.fully_minimized.insert(label) {
+                    let target = self.minimized_examples[&label].clone();
+                    let mut shrinker = Shrinker::new(self, target, move |r| {
+                        r.status == Status::Interesting(label)
+                    });
+
+                    match shrinker.run().await {
+                        // We ran out of time for this label, but may still
+                        // have time to shrink the others.
+                        Err(LoopExitReason::ShrinkLimitReached)
+                            if !self.shrink_budget_exhausted() =>
+                        {
+                            self.shrinking_cut_short = true;
+                        }
+                        result => result?,
+                    }
                 }
             }
         }
 
-        Err(LoopExitReason::Complete)
+        if self.shrinking_cut_short {
+            Err(LoopExitReason::ShrinkLimitReached)
+        } else {
+            Err(LoopExitReason::Complete)
+        }
     }
 
-    fn generate_examples(&mut self) -> Result<TestResult, LoopExitReason> {
-        while self.valid_examples < self.max_examples
-            && self.invalid_examples < 10 * self.max_examples
+    // True if we have used up all of the time or test calls that we
+    // are allowed to spend on shrinking as a whole.
+    fn shrink_budget_exhausted(&self) -> bool {
+        let calls = self.calls - self.calls_before_shrinking;
+        let elapsed = self.shrinking_started.map(|t| t.elapsed());
+        self.settings.max_shrinks.is_some_and(|m| calls >= m)
+            || self
+                .settings
+                .max_total_shrink_time
+                .is_some_and(|m| elapsed.is_some_and(|e| e >= m))
+    }
+
+    async fn generate_examples(&mut self) -> StepResult {
+        while self.valid_examples < self.settings.max_examples
+            && self.invalid_examples + self.overflow_examples < 10 * self.settings.max_examples
         {
+            if self.tree.is_exhausted() {
+                return Err(LoopExitReason::Exhausted);
+            }
+            let prefix = self.generate_prefix();
             let r = self.random.gen();
-            let result = self.execute(DataSource::from_random(r))?;
+            let start = Instant::now();
+            let result = self
+                .execute(DataSource::from_prefix_and_random(prefix, r))
+                .await?;
+            // Once generation has found a bug there's no point
+            // complaining about how hard it was to find.
             if let Status::Interesting(_) = result.status {
-                return Ok(result);
+                return Ok(());
             }
+            self.run_health_check(&result, start.elapsed())?;
         }
-        Err(LoopExitReason::MaxExamples)
+        Ok(())
     }
 
-    fn execute(&mut self, source: DataSource) -> Result<TestResult, LoopExitReason> {
-        let result = match self.sender.send(LoopCommand::RunThis(source)) {
-            Ok(_) => match self.receiver.recv() {
-                Ok(t) => t,
-                Err(_) => return Err(LoopExitReason::Shutdown),
-            },
-            Err(_) => return Err(LoopExitReason::Shutdown),
+    // The examples that the Mutate phase starts from, in a consistent
+    // order so that runs are reproducible.
+    fn mutation_seeds(&self) -> Vec<TestResult> {
+        let mut labels: Vec<&u64> = self.minimized_examples.keys().collect();
+        labels.sort();
+        let mut targets: Vec<&String> = self.best_targets.keys().collect();
+        targets.sort();
+        labels
+            .into_iter()
+            .map(|label| self.minimized_examples[label].clone())
+            .chain(targets.into_iter().map(|t| self.best_targets[t].1.clone()))
+            .collect()
+    }
+
+    // Mutates the draws of known failing and high scoring examples, in
+    // the hope of finding other bugs nearby. This uses up to a quarter
+    // of the test calls that generation was allowed to make.
+    async fn mutate_examples(&mut self) -> StepResult {
+        let budget = self.settings.max_examples / 4;
+        let calls_before_mutating = self.calls;
+        let mut attempts = 0;
+        while self.calls - calls_before_mutating < budget && attempts < 10 * budget {
+            attempts += 1;
+            let seeds = self.mutation_seeds();
+            if seeds.is_empty() {
+                return Ok(());
+            }
+            let target = &seeds[self.random.gen_range(0, seeds.len())];
+            let mut prefix = mutator::mutate_draws(&mut self.random, target, &seeds);
+            prefix.truncate(self.settings.max_choices);
+            let r = self.random.gen();
+            self.execute(DataSource::from_prefix_and_random(prefix, r))
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Once we have a corpus of examples that found new coverage we spend
+    // half our time mutating them, and otherwise look for data that we
+    // have never tried before.
+    fn generate_prefix(&mut self) -> DataStream {
+        if !self.corpus.is_empty() && self.random.gen() {
+            let i = self.random.gen_range(0, self.corpus.len());
+            let mut prefix = mutator::mutate(&mut self.random, &self.corpus[i], &self.corpus);
+            prefix.truncate(self.settings.max_choices);
+            prefix
+        } else {
+            self.tree.generate_novel_prefix(&mut self.random)
+        }
+    }
+
+    // Hill climbs on each target label in turn, trying to move each
+    // value in the best example for it up or down in steps that grow
+    // while they keep improving the score and shrink when they stop.
+    // This stops as soon as we find an interesting example, or when we
+    // have used up as many test calls as generation was allowed to make.
+    async fn optimise_targets(&mut self) -> StepResult {
+        self.calls_before_targeting = self.calls;
+        let mut labels: Vec<String> = self.best_targets.keys().cloned().collect();
+        labels.sort();
+        for label in labels {
+            let mut improved = true;
+            while improved && !self.targeting_finished() {
+                improved = false;
+                let mut i = 0;
+                while i < self.best_targets[&label].1.record.len() {
+                    for &up in &[true, false] {
+                        let mut step = 1;
+                        while step > 0 && !self.targeting_finished() {
+                            if self.try_target_step(&label, i, up, step).await? {
+                                improved = true;
+                                step = step.saturating_mul(2);
+                            } else {
+                                step /= 2;
+                            }
+                        }
+                    }
+                    i += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn targeting_finished(&self) -> bool {
+        self.interesting_examples > 0
+            || self.calls - self.calls_before_targeting >= self.settings.max_examples
+    }
+
+    // Tries moving the i'th value of the best example for label by step,
+    // returning true if that improved its score.
+    async fn try_target_step(
+        &mut self,
+        label: &str,
+        i: usize,
+        up: bool,
+        step: u64,
+    ) -> Result<bool, LoopExitReason> {
+        let (best_score, ref best) = self.best_targets[label];
+        if i >= best.record.len() || best.written_indices.contains(&i) {
+            return Ok(false);
+        }
+        let max_value = datatree::mask(u64::MAX, best.sizes[i]);
+        let value = if up {
+            match best.record[i].checked_add(step) {
+                Some(v) if v <= max_value => v,
+                _ => return Ok(false),
+            }
+        } else {
+            match best.record[i].checked_sub(step) {
+                Some(v) => v,
+                None => return Ok(false),
+            }
         };
+        let mut attempt = best.record.clone();
+        attempt[i] = value;
+        let r = self.random.gen();
+        self.execute(DataSource::from_prefix_and_random(attempt, r))
+            .await?;
+        Ok(self.best_targets[label].0 > best_score)
+    }
+
+    fn run_health_check(&mut self, result: &TestResult, elapsed: Duration) -> StepResult {
+        if let Some(ref mut state) = self.health_check {
+            state.record(result, elapsed);
+            let suppressed = &self.settings.suppress_health_check;
+            if let Some(failure) = state
+                .failures()
+                .into_iter()
+                .find(|check| !suppressed.contains(check))
+            {
+                return Err(LoopExitReason::HealthCheckFailed(failure));
+            }
+            if state.is_finished() {
+                self.health_check = None;
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self, source: DataSource) -> Result<TestResult, LoopExitReason> {
+        let source = source.with_max_size(self.settings.max_choices);
+        let cached = match source.buffer() {
+            Some(buf) => self.tree.lookup(buf),
+            // A random source's prefix may be enough to determine the
+            // result, e.g. if it is a mutation of something we've run.
+            None => self
+                .tree
+                .lookup(source.prefix())
+                .filter(|result| result.status != Status::Overflow),
+        };
+        if let Some(result) = cached {
+            return Ok(result);
+        }
+
+        self.calls += 1;
+        self.exchange.borrow_mut().request = Some(source);
+        let result = AwaitResult {
+            exchange: self.exchange.clone(),
+        }
+        .await;
+        self.tree.add(&result);
+        if result.status != Status::Overflow && !result.features.is_subset(&self.seen_features) {
+            self.seen_features.extend(result.features.iter().copied());
+            self.corpus.push(result.record.clone());
+        }
+        for (label, &score) in &result.target_observations {
+            let improved = self
+                .best_targets
+                .get(label)
+                .is_none_or(|&(best, _)| score > best);
+            if improved {
+                self.best_targets
+                    .insert(label.clone(), (score, result.clone()));
+            }
+        }
+        if let Some((ref phase, _)) = self.current_phase {
+            self.statistics
+                .phases
+                .entry(phase.clone())
+                .or_default()
+                .record(&result);
+        }
         match result.status {
-            Status::Overflow => (),
+            Status::Overflow => self.overflow_examples += 1,
             Status::Invalid => self.invalid_examples += 1,
             Status::Valid => self.valid_examples += 1,
             Status::Interesting(n) => {
@@ -181,12 +791,13 @@ impl MainGenerationLoop {
                 let minimized_examples = &mut self.minimized_examples;
                 let database = &mut self.database;
                 let name = &self.name;
+                let order = &self.settings.shrink_order;
 
                 minimized_examples
                     .entry(n)
                     .or_insert_with(|| result.clone());
                 minimized_examples.entry(n).and_modify(|e| {
-                    if result < *e {
+                    if order.cmp(&result, e) == Ordering::Less {
                         changed = true;
                         database.delete(name, &u64s_to_bytes(&(*e.record)));
                         *e = result.clone()
@@ -204,11 +815,31 @@ impl MainGenerationLoop {
     }
 }
 
+// The start and end of a draw within a record.
+type Span = (usize, usize);
+
+// Returns record with the contents of each of spans, which must be
+// in order and not overlap, replaced by the corresponding contents.
+fn replace_spans(record: &DataStreamSlice, spans: &[Span], contents: &[&[u64]]) -> DataStream {
+    let mut result = DataStream::with_capacity(record.len());
+    let mut prev = 0;
+    for (&(start, end), values) in spans.iter().zip(contents) {
+        result.extend_from_slice(&record[prev..start]);
+        result.extend_from_slice(values);
+        prev = end;
+    }
+    result.extend_from_slice(&record[prev..]);
+    result
+}
+
 struct Shrinker<'owner, Predicate> {
     _predicate: Predicate,
     shrink_target: TestResult,
     changes: u64,
     expensive_passes_enabled: bool,
+    deadline: Option<Instant>,
+    // The shrink pass currently running, for statistics.
+    current_pass: ShrinkPass,
     main_loop: &'owner mut MainGenerationLoop,
 }
 
@@ -222,7 +853,13 @@ where
         predicate: Predicate,
     ) -> Shrinker<'owner, Predicate> {
         assert!(predicate(&shrink_target));
+        let deadline = main_loop
+            .settings
+            .max_shrink_time_per_label
+            .map(|t| Instant::now() + t);
         Shrinker {
+            deadline,
+            current_pass: ShrinkPass::AdaptiveDelete,
             main_loop,
             _predicate: predicate,
             shrink_target,
@@ -233,51 +870,107 @@ where
 
     fn predicate(&mut self, result: &TestResult) -> bool {
         let succeeded = (self._predicate)(result);
+        // In the presence of writes it may be the case that we thought
+        // we were going to shrink this but didn't actually succeed because
+        // the written value was used.
         if succeeded
-            && (
-                // In the presence of writes it may be the case that we thought
-                // we were going to shrink this but didn't actually succeed because
-                // the written value was used.
-                result.record.len() < self.shrink_target.record.len()
-                    || (result.record.len() == self.shrink_target.record.len()
-                        && result.record < self.shrink_target.record)
-            )
+            && self
+                .main_loop
+                .settings
+                .shrink_order
+                .cmp(result, &self.shrink_target)
+                == Ordering::Less
         {
             self.changes += 1;
             self.shrink_target = result.clone();
+            self.main_loop
+                .statistics
+                .shrink_passes
+                .entry(self.current_pass)
+                .or_default()
+                .successes += 1;
         }
         succeeded
     }
 
-    fn run(&mut self) -> StepResult {
-        let mut prev = self.changes + 1;
+    // Runs shrink passes until none of them make progress. Passes
+    // that have been productive so far in this run are tried first,
+    // and whenever a pass makes progress we start again from the most
+    // productive pass, so passes that rarely help (often the expensive
+    // ones) are run much less often than passes that do.
+    async fn run(&mut self) -> StepResult {
+        loop {
+            let mut schedule: Vec<ShrinkPass> = self
+                .main_loop
+                .settings
+                .shrink_passes
+                .iter()
+                .copied()
+                .filter(|pass| self.expensive_passes_enabled || !pass.is_expensive())
+                .collect();
+            schedule.sort_by(|a, b| {
+                self.pass_efficiency(*b)
+                    .total_cmp(&self.pass_efficiency(*a))
+            });
+
+            let mut made_progress = false;
+            for pass in schedule {
+                let prev = self.changes;
+                self.run_pass(pass).await?;
+                if self.changes != prev {
+                    made_progress = true;
+                    break;
+                }
+            }
 
-        while prev != self.changes {
-            prev = self.changes;
-            self.adaptive_delete()?;
-            self.minimize_individual_blocks()?;
-            self.minimize_duplicated_blocks()?;
-            if prev == self.changes {
+            if !made_progress {
+                if self.expensive_passes_enabled
+                    || !self
+                        .main_loop
+                        .settings
+                        .shrink_passes
+                        .iter()
+                        .any(|pass| pass.is_expensive())
+                {
+                    return Ok(());
+                }
                 self.expensive_passes_enabled = true;
             }
-            if !self.expensive_passes_enabled {
-                continue;
-            }
+        }
+    }
 
-            self.reorder_blocks()?;
-            self.lower_and_delete()?;
-            self.delete_all_ranges()?;
+    // The fraction of the attempts a pass has made so far in this run
+    // that succeeded. Passes that have never been run count as fully
+    // efficient so that they get a chance to show what they can do.
+    fn pass_efficiency(&self, pass: ShrinkPass) -> f64 {
+        match self.main_loop.statistics.shrink_passes.get(&pass) {
+            Some(stats) if stats.attempts > 0 => stats.successes as f64 / stats.attempts as f64,
+            _ => 1.0,
         }
-        Ok(())
     }
 
-    fn lower_and_delete(&mut self) -> StepResult {
+    async fn run_pass(&mut self, pass: ShrinkPass) -> StepResult {
+        self.current_pass = pass;
+        match pass {
+            ShrinkPass::AdaptiveDelete => self.adaptive_delete().await,
+            ShrinkPass::MinimizeIndividualBlocks => self.minimize_individual_blocks().await,
+            ShrinkPass::MinimizeDuplicatedBlocks => self.minimize_duplicated_blocks().await,
+            ShrinkPass::ReorderBlocks => self.reorder_blocks().await,
+            ShrinkPass::LowerAndDelete => self.lower_and_delete().await,
+            ShrinkPass::DeleteAllRanges => self.delete_all_ranges().await,
+            ShrinkPass::SortSiblingDraws => self.sort_sibling_draws().await,
+            ShrinkPass::MinimizeDrawPairs => self.minimize_draw_pairs().await,
+            ShrinkPass::MinimizeFloats => self.minimize_floats().await,
+        }
+    }
+
+    async fn lower_and_delete(&mut self) -> StepResult {
         let mut i = 0;
         while i < self.shrink_target.record.len() {
             if self.shrink_target.record[i] > 0 {
                 let mut attempt = self.shrink_target.record.clone();
                 attempt[i] -= 1;
-                let (succeeded, result) = self.execute(&attempt)?;
+                let (succeeded, result) = self.execute(attempt.clone()).await?;
                 if !succeeded && result.record.len() < self.shrink_target.record.len() {
                     let mut j = 0;
                     while j < self.shrink_target.draws.len() {
@@ -289,7 +982,7 @@ where
                         if d.start > i {
                             let mut attempt2 = attempt.clone();
                             attempt2.drain(d.start..d.end);
-                            if self.incorporate(&attempt2)? {
+                            if self.incorporate(attempt2).await? {
                                 break;
                             }
                         }
@@ -302,7 +995,7 @@ where
         Ok(())
     }
 
-    fn reorder_blocks(&mut self) -> StepResult {
+    async fn reorder_blocks(&mut self) -> StepResult {
         let mut i = 0;
         while i < self.shrink_target.record.len() {
             let mut j = i + 1;
@@ -314,7 +1007,7 @@ where
                 if self.shrink_target.record[j] < self.shrink_target.record[i] {
                     let mut attempt = self.shrink_target.record.clone();
                     attempt.swap(i, j);
-                    self.incorporate(&attempt)?;
+                    self.incorporate(attempt).await?;
                 }
                 j += 1;
             }
@@ -323,7 +1016,7 @@ where
         Ok(())
     }
 
-    fn try_delete_range(
+    async fn try_delete_range(
         &mut self,
         target: &TestResult,
         i: usize,
@@ -352,11 +1045,11 @@ where
         if attempt.len() >= self.shrink_target.record.len() {
             Ok(false)
         } else {
-            self.incorporate(&attempt)
+            self.incorporate(attempt).await
         }
     }
 
-    fn adaptive_delete(&mut self) -> StepResult {
+    async fn adaptive_delete(&mut self) -> StepResult {
         let mut i = 0;
         let target = self.shrink_target.clone();
 
@@ -374,8 +1067,10 @@ where
             // move on to anything big. This is because if we try to be
             // aggressive too early on we'll tend to find that we lose out when
             // the example is "nearly minimal".
-            if self.try_delete_range(&target, i, 2)? {
-                if self.try_delete_range(&target, i, 3)? && self.try_delete_range(&target, i, 4)? {
+            if self.try_delete_range(&target, i, 2).await? {
+                if self.try_delete_range(&target, i, 3).await?
+                    && self.try_delete_range(&target, i, 4).await?
+                {
                     let mut hi = 5;
                     // At this point it looks like we've got a pretty good
                     // opportunity for a long run here. We do an exponential
@@ -386,7 +1081,7 @@ where
                     // it is, the subsequent example is going to be so tiny that
                     // it doesn't really matter if we waste a bit of extra time
                     // here.
-                    while self.try_delete_range(&target, i, hi)? {
+                    while self.try_delete_range(&target, i, hi).await? {
                         assert!(hi <= target.draws.len());
                         hi *= 2;
                     }
@@ -397,7 +1092,7 @@ where
                     let mut lo = 4;
                     while lo + 1 < hi {
                         let mid = lo + (hi - lo) / 2;
-                        if self.try_delete_range(&target, i, mid)? {
+                        if self.try_delete_range(&target, i, mid).await? {
                             lo = mid;
                         } else {
                             hi = mid;
@@ -405,7 +1100,7 @@ where
                     }
                 }
             } else {
-                self.try_delete_range(&target, i, 1)?;
+                self.try_delete_range(&target, i, 1).await?;
             }
             // We unconditionally bump i because we have always tried deleting
             // one more example than we succeeded at deleting, so we expect the
@@ -415,7 +1110,7 @@ where
         Ok(())
     }
 
-    fn delete_all_ranges(&mut self) -> StepResult {
+    async fn delete_all_ranges(&mut self) -> StepResult {
         let mut i = 0;
         while i < self.shrink_target.record.len() {
             let start_length = self.shrink_target.record.len();
@@ -426,7 +1121,7 @@ where
                 let mut attempt = self.shrink_target.record.clone();
                 attempt.drain(i..j);
                 assert!(attempt.len() + (j - i) == self.shrink_target.record.len());
-                let deleted = self.incorporate(&attempt)?;
+                let deleted = self.incorporate(attempt).await?;
                 if !deleted {
                     j += 1;
                 }
@@ -438,33 +1133,36 @@ where
         Ok(())
     }
 
-    fn try_lowering_value(&mut self, i: usize, v: u64) -> Result<bool, LoopExitReason> {
+    async fn try_lowering_value(&mut self, i: usize, v: u64) -> Result<bool, LoopExitReason> {
         if v >= self.shrink_target.record[i] {
             return Ok(false);
         }
 
         let mut attempt = self.shrink_target.record.clone();
         attempt[i] = v;
-        let (succeeded, result) = self.execute(&attempt)?;
+        let (succeeded, result) = self.execute(attempt.clone()).await?;
         assert!(result.record.len() <= self.shrink_target.record.len());
         let lost_bytes = self.shrink_target.record.len() - result.record.len();
         if !succeeded && result.status == Status::Valid && lost_bytes > 0 {
             attempt.drain(i + 1..i + lost_bytes + 1);
             assert!(attempt.len() + lost_bytes == self.shrink_target.record.len());
-            self.incorporate(&attempt)
+            self.incorporate(attempt).await
         } else {
             Ok(succeeded)
         }
     }
 
-    fn minimize_individual_blocks(&mut self) -> StepResult {
+    async fn minimize_individual_blocks(&mut self) -> StepResult {
         let mut i = 0;
 
         while i < self.shrink_target.record.len() {
             if !self.shrink_target.written_indices.contains(&i) {
-                minimize_integer(self.shrink_target.record[i], |v| {
-                    self.try_lowering_value(i, v)
-                })?;
+                let start = self.shrink_target.record[i];
+                let shrinker = &mut *self;
+                minimize_integer(start, async move |v| {
+                    shrinker.try_lowering_value(i, v).await
+                })
+                .await?;
             }
 
             i += 1;
@@ -502,7 +1200,7 @@ where
         result
     }
 
-    fn minimize_duplicated_blocks(&mut self) -> StepResult {
+    async fn minimize_duplicated_blocks(&mut self) -> StepResult {
         let mut i = 0;
         let mut targets = self.calc_duplicates();
 
@@ -514,16 +1212,18 @@ where
             assert!(!target.is_empty());
             let v = self.shrink_target.record[target[0]];
 
-            let w = minimize_integer(v, |t| {
-                if max_target >= self.shrink_target.record.len() {
+            let shrinker = &mut *self;
+            let w = minimize_integer(v, async move |t| {
+                if max_target >= shrinker.shrink_target.record.len() {
                     return Ok(false);
                 }
-                let mut attempt = self.shrink_target.record.clone();
+                let mut attempt = shrinker.shrink_target.record.clone();
                 for i in &target {
                     attempt[*i] = t
                 }
-                self.incorporate(&attempt)
-            })?;
+                shrinker.incorporate(attempt).await
+            })
+            .await?;
             if w != v {
                 targets = self.calc_duplicates();
             }
@@ -531,13 +1231,184 @@ where
         Ok(())
     }
 
-    fn execute(&mut self, buf: &DataStreamSlice) -> Result<(bool, TestResult), LoopExitReason> {
-        // TODO: Later there will be caching here
-        let result = self.main_loop.execute(DataSource::from_vec(buf.to_vec()))?;
+    // Groups the spans of draws that have the same label and are
+    // directly inside the same draw, e.g. the elements of a list. Each
+    // group is in the order the draws were made.
+    fn sibling_draws(&self) -> Vec<Vec<Span>> {
+        let draws = &self.shrink_target.draws;
+        let mut groups: HashMap<(Option<usize>, u64), Vec<Span>> = HashMap::new();
+        for (i, draw) in draws.iter().enumerate() {
+            // Draws are recorded in the order they started, so the
+            // parent of a draw is the closest earlier draw one level up.
+            let parent = if draw.depth == 0 {
+                None
+            } else {
+                (0..i).rev().find(|&j| draws[j].depth + 1 == draw.depth)
+            };
+            groups
+                .entry((parent, draw.label))
+                .or_default()
+                .push((draw.start, draw.end));
+        }
+        let mut result: Vec<Vec<Span>> = groups
+            .drain()
+            .map(|(_, spans)| spans)
+            .filter(|spans| spans.len() > 1)
+            .collect();
+        result.sort();
+        result
+    }
+
+    // Puts the contents of sibling draws in sorted order, so that e.g.
+    // the elements of a list end up sorted when the order of them
+    // doesn't matter. If sorting a whole group doesn't work we fall
+    // back to swapping adjacent draws that are out of order.
+    async fn sort_sibling_draws(&mut self) -> StepResult {
+        let mut i = 0;
+        let mut groups = self.sibling_draws();
+        while i < groups.len() {
+            let spans = groups[i].clone();
+            i += 1;
+
+            let record = &self.shrink_target.record;
+            let mut contents: Vec<&[u64]> = spans.iter().map(|&(u, v)| &record[u..v]).collect();
+            contents.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+            let attempt = replace_spans(record, &spans, &contents);
+            if attempt < *record && self.incorporate(attempt).await? {
+                groups = self.sibling_draws();
+                i = 0;
+                continue;
+            }
+
+            for k in 1..spans.len() {
+                let record = &self.shrink_target.record;
+                if spans[k].1 > record.len() {
+                    break;
+                }
+                let mut contents: Vec<&[u64]> = spans.iter().map(|&(u, v)| &record[u..v]).collect();
+                contents.swap(k - 1, k);
+                let attempt = replace_spans(record, &spans, &contents);
+                if attempt < *record && self.incorporate(attempt).await? {
+                    groups = self.sibling_draws();
+                    i = 0;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Tries to move value from one sibling draw to a later one, by
+    // lowering a value in the first while raising the value in the
+    // same position of the second by the same amount. This shrinks
+    // examples where the test depends on something like the sum of
+    // the draws, which lowering values one at a time can't.
+    async fn minimize_draw_pairs(&mut self) -> StepResult {
+        for spans in self.sibling_draws() {
+            for a in 0..spans.len() {
+                for b in a + 1..spans.len() {
+                    let (a_start, a_end) = spans[a];
+                    let (b_start, b_end) = spans[b];
+                    if a_end - a_start != b_end - b_start {
+                        continue;
+                    }
+                    for k in 0..a_end - a_start {
+                        let (i, j) = (a_start + k, b_start + k);
+                        if j >= self.shrink_target.record.len()
+                            || self.shrink_target.written_indices.contains(&i)
+                            || self.shrink_target.written_indices.contains(&j)
+                        {
+                            continue;
+                        }
+                        let v = self.shrink_target.record[i];
+                        let w = self.shrink_target.record[j];
+                        let shrinker = &mut *self;
+                        minimize_integer(v, async move |t| {
+                            let moved = w.checked_add(v - t);
+                            if moved.is_none() || j >= shrinker.shrink_target.record.len() {
+                                return Ok(false);
+                            }
+                            let mut attempt = shrinker.shrink_target.record.clone();
+                            attempt[i] = t;
+                            attempt[j] = moved.unwrap();
+                            shrinker.incorporate(attempt).await
+                        })
+                        .await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The indices of the encoded magnitudes of floats drawn with
+    // distributions::floats.
+    fn float_indices(&self) -> Vec<usize> {
+        self.shrink_target
+            .draws
+            .iter()
+            .filter(|draw| draw.label == FLOAT_LABEL && draw.end == draw.start + 2)
+            .map(|draw| draw.start + 1)
+            .collect()
+    }
+
+    // Tries to make floats simpler by removing fractional bits from
+    // them. Floats that are already integers are encoded as those
+    // integers, so minimize_individual_blocks takes care of them.
+    async fn minimize_floats(&mut self) -> StepResult {
+        let mut k = 0;
+        loop {
+            let indices = self.float_indices();
+            if k >= indices.len() {
+                break;
+            }
+            let i = indices[k];
+            k += 1;
+
+            let lex = self.shrink_target.record[i];
+            let f = lex_to_float(lex);
+            let candidates: Vec<f64> = if f.is_finite() {
+                (0..10)
+                    .map(|p| {
+                        let scale = (1u64 << p) as f64;
+                        (f * scale).floor() / scale
+                    })
+                    .collect()
+            } else {
+                vec![f64::MAX]
+            };
+            for candidate in candidates {
+                let encoded = float_to_lex(candidate);
+                if encoded >= lex {
+                    continue;
+                }
+                let mut attempt = self.shrink_target.record.clone();
+                attempt[i] = encoded;
+                if self.incorporate(attempt).await? {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self, buf: DataStream) -> Result<(bool, TestResult), LoopExitReason> {
+        if self.main_loop.shrink_budget_exhausted()
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            return Err(LoopExitReason::ShrinkLimitReached);
+        }
+        self.main_loop
+            .statistics
+            .shrink_passes
+            .entry(self.current_pass)
+            .or_default()
+            .attempts += 1;
+        let result = self.main_loop.execute(DataSource::from_vec(buf)).await?;
         Ok((self.predicate(&result), result))
     }
 
-    fn incorporate(&mut self, buf: &DataStreamSlice) -> Result<bool, LoopExitReason> {
+    async fn incorporate(&mut self, buf: DataStream) -> Result<bool, LoopExitReason> {
         assert!(
             buf.len() <= self.shrink_target.record.len(),
             "Expected incorporate to not increase length, but buf.len() = {} \
@@ -546,12 +1417,12 @@ where
             self.shrink_target.record.len()
         );
         if buf.len() == self.shrink_target.record.len() {
-            assert!(buf < &self.shrink_target.record);
+            assert!(buf < self.shrink_target.record);
         }
-        if self.shrink_target.record.starts_with(buf) {
+        if self.shrink_target.record.starts_with(&buf) {
             return Ok(false);
         }
-        let (succeeded, _) = self.execute(buf)?;
+        let (succeeded, _) = self.execute(buf).await?;
         Ok(succeeded)
     }
 }
@@ -562,77 +1433,72 @@ enum EngineState {
     ReadyToProvide,
 }
 
-#[derive(Debug)]
-pub struct Engine {
-    // The next response from the main loop. Once
-    // this is set to Some(Finished(_)) it stays that way,
-    // otherwise it is cleared on access.
-    loop_response: Option<LoopCommand>,
-
-    state: EngineState,
+type MainLoopFuture = Pin<Box<dyn Future<Output = (LoopExitReason, MainGenerationLoop)>>>;
 
-    // Communication channels with the main testing loop
-    handle: Option<thread::JoinHandle<()>>,
-    receiver: Receiver<LoopCommand>,
-    sender: SyncSender<TestResult>,
+enum LoopState {
+    Running(MainLoopFuture),
+    Finished(LoopExitReason, Box<MainGenerationLoop>),
 }
 
-fn bytes_to_u64s(bytes: &[u8]) -> Vec<u64> {
-    let mut reader = io::Cursor::new(bytes);
-    let mut result = Vec::new();
-    while let Ok(n) = reader.read_u64::<BigEndian>() {
-        result.push(n);
+impl fmt::Debug for LoopState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoopState::Running(_) => f.write_str("Running"),
+            LoopState::Finished(reason, main_loop) => f
+                .debug_tuple("Finished")
+                .field(reason)
+                .field(main_loop)
+                .finish(),
+        }
     }
-    result
 }
 
-fn u64s_to_bytes(ints: &[u64]) -> Vec<u8> {
-    let mut result = Vec::new();
-    for n in ints {
-        result.write_u64::<BigEndian>(*n).unwrap();
-    }
-    result
+#[derive(Debug)]
+pub struct Engine {
+    // The main loop, either suspended waiting on a test result
+    // or finished. Once it is Finished it stays that way.
+    loop_state: LoopState,
+
+    state: EngineState,
+
+    // Communication with the main loop while it is running.
+    exchange: SharedExchange,
 }
 
 impl Engine {
-    pub fn new(
-        name: String,
-        max_examples: u64,
-        phases: Vec<Phase>,
-        seed: &[u32],
-        db: BoxedDatabase,
-    ) -> Engine {
-        let (send_local, recv_remote) = sync_channel(1);
-        let (send_remote, recv_local) = sync_channel(1);
+    pub fn new(name: String, settings: Settings, seed: &[u32], db: BoxedDatabase) -> Engine {
+        let exchange = SharedExchange::default();
 
         let main_loop = MainGenerationLoop {
             database: db,
             name,
-            max_examples,
-            phases,
+            settings,
             random: ChaChaRng::from_seed(seed),
-            sender: send_remote,
-            receiver: recv_remote,
+            exchange: exchange.clone(),
+            tree: DataTree::new(),
             best_example: None,
             minimized_examples: HashMap::new(),
             fully_minimized: HashSet::new(),
             valid_examples: 0,
             invalid_examples: 0,
+            overflow_examples: 0,
             interesting_examples: 0,
+            health_check: Some(HealthCheckState::default()),
+            corpus: Vec::new(),
+            seen_features: HashSet::new(),
+            best_targets: HashMap::new(),
+            statistics: Statistics::default(),
+            current_phase: None,
+            calls: 0,
+            calls_before_shrinking: 0,
+            calls_before_targeting: 0,
+            shrinking_started: None,
+            shrinking_cut_short: false,
         };
 
-        let handle = thread::Builder::new()
-            .name("Hypothesis main loop".to_string())
-            .spawn(move || {
-                main_loop.run();
-            })
-            .unwrap();
-
         Engine {
-            loop_response: None,
-            sender: send_local,
-            receiver: recv_local,
-            handle: Some(handle),
+            loop_state: LoopState::Running(Box::pin(main_loop.run())),
+            exchange,
             state: EngineState::ReadyToProvide,
         }
     }
@@ -641,36 +1507,43 @@ impl Engine {
         self.consume_test_result(source.into_result(status))
     }
 
+    // Like mark_finished, but also reports the coverage features that
+    // the test execution reached. Examples that reach new features are
+    // kept and mutated to generate new examples, which makes the engine
+    // behave like a coverage-guided fuzzer.
+    pub fn mark_finished_with_features(
+        &mut self,
+        source: DataSource,
+        status: Status,
+        features: HashSet<u64>,
+    ) {
+        let mut result = source.into_result(status);
+        result.features = features;
+        self.consume_test_result(result)
+    }
+
     pub fn next_source(&mut self) -> Option<DataSource> {
         assert!(self.state == EngineState::ReadyToProvide);
         self.state = EngineState::AwaitingCompletion;
 
-        self.await_loop_response();
-
-        let mut local_result = None;
-        mem::swap(&mut local_result, &mut self.loop_response);
+        self.resume_loop();
 
-        match local_result {
-            Some(LoopCommand::RunThis(source)) => Some(source),
-            None => panic!("BUG: Loop response should not be empty at this point"),
-            _ => {
-                self.loop_response = local_result;
-                None
-            }
+        match self.loop_state {
+            LoopState::Running(_) => match self.exchange.borrow_mut().request.take() {
+                Some(source) => Some(source),
+                None => panic!("BUG: Main loop suspended without requesting a test execution"),
+            },
+            LoopState::Finished(..) => None,
         }
     }
 
     pub fn list_minimized_examples(&self) -> Vec<TestResult> {
-        match self.loop_response {
-            Some(LoopCommand::Finished(
-                _,
-                MainGenerationLoop {
-                    ref minimized_examples,
-                    ..
-                },
-            )) => {
-                let mut results: Vec<TestResult> = minimized_examples.values().cloned().collect();
-                results.sort();
+        match self.loop_state {
+            LoopState::Finished(_, ref main_loop) => {
+                let mut results: Vec<TestResult> =
+                    main_loop.minimized_examples.values().cloned().collect();
+                let order = &main_loop.settings.shrink_order;
+                results.sort_by(|a, b| order.cmp(a, b));
                 results
             }
             _ => Vec::new(),
@@ -678,14 +1551,13 @@ impl Engine {
     }
 
     pub fn best_source(&self) -> Option<DataSource> {
-        match self.loop_response {
-            Some(LoopCommand::Finished(
-                _,
-                MainGenerationLoop {
-                    best_example: Some(ref result),
-                    ..
-                },
-            )) => Some(DataSource::from_vec(result.record.clone())),
+        match self.loop_state {
+            LoopState::Finished(_, ref main_loop) => {
+                main_loop.best_example.as_ref().map(|result| {
+                    DataSource::from_vec(result.record.clone())
+                        .with_max_size(main_loop.settings.max_choices)
+                })
+            }
             _ => None,
         }
     }
@@ -698,60 +1570,59 @@ impl Engine {
             return;
         }
 
-        // NB: Deliberately not matching on result. If this fails,
-        // that's OK - it means the loop has shut down and when we ask
-        // for data from it we'll get its shutdown response.
-        let _ = self.sender.send(result);
+        self.exchange.borrow_mut().response = Some(result);
+    }
+
+    // Why the engine stopped, or None if it is still running.
+    pub fn exit_reason(&self) -> Option<LoopExitReason> {
+        match self.loop_state {
+            LoopState::Finished(ref reason, _) => Some(reason.clone()),
+            _ => None,
+        }
+    }
+
+    // What the engine did during the run, once it has finished.
+    pub fn statistics(&self) -> Option<Statistics> {
+        match self.loop_state {
+            LoopState::Finished(ref reason, ref main_loop) => Some(Statistics {
+                exit_reason: Some(reason.clone()),
+                ..main_loop.statistics.clone()
+            }),
+            _ => None,
+        }
+    }
+
+    // The health check that stopped the engine, if any.
+    pub fn health_check_failure(&self) -> Option<HealthCheck> {
+        match self.exit_reason() {
+            Some(LoopExitReason::HealthCheckFailed(check)) => Some(check),
+            _ => None,
+        }
     }
 
     pub fn was_unsatisfiable(&self) -> bool {
-        match self.loop_response {
-            Some(LoopCommand::Finished(_, ref main_loop)) => {
-                main_loop.interesting_examples == 0 && main_loop.valid_examples == 0
+        match self.loop_state {
+            LoopState::Finished(_, ref main_loop) => {
+                main_loop.interesting_examples == 0
+                    && main_loop.valid_examples == 0
+                    && main_loop.settings.phases.contains(&Phase::Generate)
             }
             _ => false,
         }
     }
 
     fn has_shutdown(&mut self) -> bool {
-        matches!(self.loop_response, Some(LoopCommand::Finished(..)))
-    }
-
-    fn await_thread_termination(&mut self) {
-        let mut maybe_handle = None;
-        mem::swap(&mut self.handle, &mut maybe_handle);
-        if let Some(handle) = maybe_handle {
-            if let Err(boxed_msg) = handle.join() {
-                // FIXME: This is awful but as far as I can tell this is
-                // genuinely the only way to get the actual message out of the
-                // panic in the child thread! It's boxed as an Any, and the
-                // debug of Any just says "Any". Fortunately the main loop is
-                // very much under our control so this doesn't matter too much
-                // here, but yuck!
-                if let Some(msg) = boxed_msg.downcast_ref::<&str>() {
-                    panic!(msg.to_string());
-                } else if let Some(msg) = boxed_msg.downcast_ref::<String>() {
-                    panic!(msg.clone());
-                } else {
-                    panic!("BUG: Unexpected panic format in main loop");
-                }
-            }
-        }
+        matches!(self.loop_state, LoopState::Finished(..))
     }
 
-    fn await_loop_response(&mut self) {
-        if self.loop_response.is_none() {
-            match self.receiver.recv() {
-                Ok(response) => {
-                    self.loop_response = Some(response);
-                    if self.has_shutdown() {
-                        self.await_thread_termination();
-                    }
-                }
-                Err(_) => {
-                    self.await_thread_termination();
-                    panic!("BUG: Unexpected silent termination of generation loop.")
-                }
+    // Runs the main loop until it either needs a test executed or
+    // finishes. This happens on the calling thread, so any panic in
+    // the main loop propagates directly to our caller.
+    fn resume_loop(&mut self) {
+        if let LoopState::Running(ref mut main_loop) = self.loop_state {
+            let mut context = Context::from_waker(Waker::noop());
+            if let Poll::Ready((reason, main_loop)) = main_loop.as_mut().poll(&mut context) {
+                self.loop_state = LoopState::Finished(reason, Box::new(main_loop));
             }
         }
     }
@@ -762,16 +1633,34 @@ mod tests {
     use super::*;
     use crate::data::FailedDraw;
     use crate::database::NoDatabase;
+    use crate::distributions;
 
-    fn run_to_results<F>(mut f: F) -> Vec<TestResult>
+    fn run_to_results<F>(f: F) -> Vec<TestResult>
+    where
+        F: FnMut(&mut DataSource) -> Result<Status, FailedDraw>,
+    {
+        let settings = Settings {
+            max_examples: 1000,
+            ..Settings::default()
+        };
+        run_with_settings(settings, f)
+    }
+
+    fn run_with_settings<F>(settings: Settings, f: F) -> Vec<TestResult>
+    where
+        F: FnMut(&mut DataSource) -> Result<Status, FailedDraw>,
+    {
+        run_engine(settings, f).list_minimized_examples()
+    }
+
+    fn run_engine<F>(settings: Settings, mut f: F) -> Engine
     where
         F: FnMut(&mut DataSource) -> Result<Status, FailedDraw>,
     {
         let seed: [u32; 2] = [0, 0];
         let mut engine = Engine::new(
             "run_to_results".to_string(),
-            1000,
-            Phase::all(),
+            settings,
             &seed,
             Box::new(NoDatabase),
         );
@@ -782,7 +1671,7 @@ mod tests {
                 engine.mark_finished(source, Status::Overflow);
             }
         }
-        engine.list_minimized_examples()
+        engine
     }
 
     #[test]
@@ -800,4 +1689,480 @@ mod tests {
         assert_eq!(results[0].record[0], 100);
         assert_eq!(results[1].record[0], 101);
     }
+
+    #[test]
+    fn does_not_rerun_the_same_data() {
+        let mut seen = HashSet::new();
+        run_to_results(|source| {
+            let n = source.bits(64)?;
+            assert!(seen.insert(n), "Ran test on {} twice", n);
+            if n >= 100 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+    }
+
+    #[test]
+    fn stops_when_search_space_is_exhausted() {
+        let mut calls = 0;
+        let results = run_to_results(|source| {
+            calls += 1;
+            distributions::bounded_int(source, 3)?;
+            Ok(Status::Valid)
+        });
+        assert!(results.is_empty());
+        assert_eq!(calls, 4);
+    }
+
+    #[test]
+    fn runs_and_shrinks_explicit_examples_without_generating() {
+        let settings = Settings {
+            phases: vec![Phase::Explicit, Phase::Shrink],
+            explicit_examples: vec![vec![1000]],
+            ..Settings::default()
+        };
+        let mut calls = 0;
+        let results = run_with_settings(settings, |source| {
+            calls += 1;
+            if source.bits(64)? >= 100 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].record, vec![100]);
+        assert!(calls < 100);
+    }
+
+    #[test]
+    fn runs_nothing_when_only_shrinking() {
+        let settings = Settings {
+            phases: vec![Phase::Shrink],
+            ..Settings::default()
+        };
+        let results = run_with_settings(settings, |_| panic!("Test was run"));
+        assert!(results.is_empty());
+    }
+
+    fn at_least_100(source: &mut DataSource) -> Result<Status, FailedDraw> {
+        if source.bits(64)? >= 100 {
+            Ok(Status::Interesting(0))
+        } else {
+            Ok(Status::Valid)
+        }
+    }
+
+    #[test]
+    fn reports_when_shrinking_is_fully_completed() {
+        let engine = run_engine(Settings::default(), at_least_100);
+        assert_eq!(engine.exit_reason(), Some(LoopExitReason::Complete));
+    }
+
+    #[test]
+    fn stops_shrinking_after_max_shrinks() {
+        let settings = Settings {
+            max_shrinks: Some(3),
+            ..Settings::default()
+        };
+        let engine = run_engine(settings, at_least_100);
+        assert_eq!(
+            engine.exit_reason(),
+            Some(LoopExitReason::ShrinkLimitReached)
+        );
+        let results = engine.list_minimized_examples();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].record[0] > 100);
+    }
+
+    #[test]
+    fn stops_shrinking_label_when_out_of_time() {
+        let settings = Settings {
+            max_shrink_time_per_label: Some(Duration::from_secs(0)),
+            ..Settings::default()
+        };
+        let engine = run_engine(settings, at_least_100);
+        assert_eq!(
+            engine.exit_reason(),
+            Some(LoopExitReason::ShrinkLimitReached)
+        );
+        assert_eq!(engine.list_minimized_examples().len(), 1);
+    }
+
+    #[test]
+    fn fails_health_check_when_filtering_too_much() {
+        let engine = run_engine(Settings::default(), |source| {
+            if source.bits(8)? == 0 {
+                Ok(Status::Valid)
+            } else {
+                Ok(Status::Invalid)
+            }
+        });
+        assert_eq!(
+            engine.health_check_failure(),
+            Some(HealthCheck::FilterTooMuch)
+        );
+    }
+
+    #[test]
+    fn fails_health_check_when_data_is_too_large() {
+        let engine = run_engine(Settings::default(), |source| {
+            for _ in 0..2000 {
+                source.bits(1)?;
+            }
+            Ok(Status::Valid)
+        });
+        assert_eq!(
+            engine.health_check_failure(),
+            Some(HealthCheck::DataTooLarge)
+        );
+    }
+
+    #[test]
+    fn slow_failing_examples_do_not_fail_health_checks() {
+        let mut first = true;
+        let engine = run_engine(Settings::default(), |source| {
+            source.bits(8)?;
+            if first {
+                first = false;
+                std::thread::sleep(HEALTH_CHECK_MAX_DURATION);
+            }
+            Ok(Status::Interesting(0))
+        });
+        assert_eq!(engine.health_check_failure(), None);
+        assert_eq!(engine.list_minimized_examples().len(), 1);
+    }
+
+    #[test]
+    fn suppressed_health_checks_do_not_stop_generation() {
+        let settings = Settings {
+            suppress_health_check: vec![HealthCheck::TooManyOverflows],
+            ..Settings::default()
+        };
+        let engine = run_engine(settings, |source| {
+            if source.bits(8)? < 200 {
+                Err(FailedDraw)
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(engine.health_check_failure(), None);
+        assert_eq!(engine.exit_reason(), Some(LoopExitReason::MaxExamples));
+    }
+
+    #[test]
+    fn stops_runaway_generators_with_overflow() {
+        let settings = Settings {
+            max_choices: 100,
+            ..Settings::default()
+        };
+        let mut longest = 0;
+        let engine = run_engine(settings, |source| {
+            let mut n = 0;
+            loop {
+                source.bits(1)?;
+                n += 1;
+                longest = longest.max(n);
+            }
+        });
+        assert_eq!(longest, 100);
+        assert_eq!(
+            engine.health_check_failure(),
+            Some(HealthCheck::TooManyOverflows)
+        );
+    }
+
+    #[test]
+    fn reports_statistics_for_each_phase() {
+        let engine = run_engine(Settings::default(), at_least_100);
+        let statistics = engine.statistics().unwrap();
+        assert_eq!(statistics.exit_reason, Some(LoopExitReason::Complete));
+
+        let generate = statistics.phase(&Phase::Generate);
+        assert_eq!(generate.interesting, 1);
+        assert_eq!(generate.calls(), generate.valid + 1);
+
+        let shrink = statistics.phase(&Phase::Shrink);
+        assert!(shrink.calls() > 0);
+        let successes: u64 = statistics
+            .shrink_passes
+            .values()
+            .map(|pass| pass.successes)
+            .sum();
+        assert!(successes > 0);
+        assert!(successes <= shrink.interesting);
+    }
+
+    #[test]
+    fn only_runs_enabled_shrink_passes() {
+        let settings = Settings {
+            shrink_passes: vec![ShrinkPass::MinimizeIndividualBlocks],
+            ..Settings::default()
+        };
+        let engine = run_engine(settings, at_least_100);
+        assert_eq!(engine.list_minimized_examples()[0].record, vec![100]);
+        let passes: Vec<ShrinkPass> = engine
+            .statistics()
+            .unwrap()
+            .shrink_passes
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(passes, vec![ShrinkPass::MinimizeIndividualBlocks]);
+    }
+
+    #[test]
+    fn does_not_shrink_without_shrink_passes() {
+        let settings = Settings {
+            shrink_passes: Vec::new(),
+            ..Settings::default()
+        };
+        let results = run_with_settings(settings, at_least_100);
+        assert!(results[0].record[0] > 100);
+    }
+
+    // Draws a list of integers, for testing passes that work on
+    // sibling draws.
+    fn draw_list(source: &mut DataSource) -> Result<Vec<u64>, FailedDraw> {
+        let mut result = Vec::new();
+        while source.bits(1)? == 1 {
+            source.start_draw(1);
+            result.push(source.bits(16)?);
+            source.stop_draw();
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn sorts_sibling_draws() {
+        // Each element is made of two values, so sorting the list
+        // can't be done by swapping individual values.
+        let results = run_to_results(|source| {
+            let mut list = Vec::new();
+            while source.bits(1)? == 1 {
+                source.start_draw(1);
+                list.push((source.bits(8)? << 8) | source.bits(8)?);
+                source.stop_draw();
+            }
+            if list.len() == 2 && list.iter().all(|&x| x >= 10) && list.iter().any(|&x| x >= 1000) {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(results.len(), 1);
+        assert_eq!(&results[0].record[..3], &[1, 0, 10]);
+    }
+
+    #[test]
+    fn moves_value_between_sibling_draws() {
+        let results = run_to_results(|source| {
+            let list = draw_list(source)?;
+            if list.len() == 2 && list.iter().sum::<u64>() > 1000 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].record, vec![1, 0, 1, 1001, 0]);
+    }
+
+    // Prefers odd values of the first choice to even ones.
+    #[derive(Debug)]
+    struct OddFirst;
+
+    impl ShrinkOrder for OddFirst {
+        fn cmp(&self, left: &TestResult, right: &TestResult) -> Ordering {
+            let even = |result: &TestResult| result.record.first().map(|v| v % 2 == 0);
+            even(left)
+                .cmp(&even(right))
+                .then_with(|| Shortlex.cmp(left, right))
+        }
+    }
+
+    #[test]
+    fn shrinks_using_the_shrink_order() {
+        let settings = Settings {
+            shrink_order: Rc::new(OddFirst),
+            ..Settings::default()
+        };
+        let results = run_with_settings(settings, |source| {
+            if source.bits(8)? >= 10 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(results[0].record, vec![11]);
+    }
+
+    #[test]
+    fn shrinks_floats_to_simple_values() {
+        let results = run_to_results(|source| {
+            let f = distributions::floats(source, 0.0, f64::MAX, false, false)?;
+            if f > 1.2 && f.fract() != 0.0 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(results[0].record, vec![0, 0, float_to_lex(1.5)]);
+    }
+
+    #[test]
+    fn removes_fractional_bits_from_floats() {
+        let settings = Settings {
+            max_examples: 1000,
+            shrink_passes: vec![ShrinkPass::MinimizeFloats],
+            ..Settings::default()
+        };
+        let results = run_with_settings(settings, |source| {
+            let f = distributions::floats(source, 0.0, f64::MAX, false, false)?;
+            if f > 1.2 && f.fract() != 0.0 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(lex_to_float(results[0].record[2]).fract(), 0.5);
+    }
+
+    #[test]
+    fn shrink_pass_names_round_trip() {
+        for pass in ShrinkPass::all() {
+            assert_eq!(ShrinkPass::try_from(pass.to_string().as_str()), Ok(pass));
+        }
+        assert!(ShrinkPass::try_from("shrink_harder").is_err());
+    }
+
+    #[test]
+    fn counts_events_in_each_phase() {
+        let engine = run_engine(Settings::default(), |source| {
+            if source.bits(1)? == 0 {
+                source.note_event("zero");
+                source.note_event("zero");
+            }
+            Ok(Status::Valid)
+        });
+        let generate = engine.statistics().unwrap().phase(&Phase::Generate);
+        assert_eq!(generate.events["zero"], 1);
+    }
+
+    #[test]
+    fn targeting_finds_examples_that_generation_misses() {
+        let high_score = |source: &mut DataSource| {
+            let n = source.bits(32)?;
+            source.target("n", n as f64);
+            if n >= u64::from(u32::MAX) - 1000 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        };
+        let settings = Settings::default();
+        let without_targeting = run_with_settings(
+            Settings {
+                phases: Phase::all()
+                    .into_iter()
+                    .filter(|p| *p != Phase::Target)
+                    .collect(),
+                ..settings.clone()
+            },
+            high_score,
+        );
+        assert!(without_targeting.is_empty());
+
+        let results = run_with_settings(settings, high_score);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].record, vec![u64::from(u32::MAX) - 1000]);
+    }
+
+    #[test]
+    fn targeting_ignores_non_finite_scores() {
+        let mut first = true;
+        let results = run_with_settings(Settings::default(), |source| {
+            let n = source.bits(32)?;
+            if first {
+                first = false;
+            } else {
+                source.target("n", n as f64);
+            }
+            source.target("n", f64::NAN);
+            source.target("n", f64::INFINITY);
+            if n >= u64::from(u32::MAX) - 1000 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].record, vec![u64::from(u32::MAX) - 1000]);
+    }
+
+    #[test]
+    fn coverage_guides_generation_towards_new_features() {
+        let seed: [u32; 2] = [0, 0];
+        let settings = Settings {
+            max_examples: 1000,
+            ..Settings::default()
+        };
+        let mut engine = Engine::new(
+            "coverage".to_string(),
+            settings,
+            &seed,
+            Box::new(NoDatabase),
+        );
+        while let Some(mut source) = engine.next_source() {
+            // A parser that only gets to the bug if it sees the
+            // right magic values, reporting how far it got.
+            let mut features = HashSet::new();
+            let mut status = Status::Interesting(0);
+            for i in 1..=4 {
+                match source.bits(4) {
+                    Ok(v) if v == i => {
+                        features.insert(i);
+                    }
+                    Ok(_) => {
+                        status = Status::Valid;
+                        break;
+                    }
+                    Err(_) => {
+                        status = Status::Overflow;
+                        break;
+                    }
+                }
+            }
+            engine.mark_finished_with_features(source, status, features);
+        }
+        let results = engine.list_minimized_examples();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].record, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn mutation_finds_bugs_near_known_bugs() {
+        let engine = run_engine(Settings::default(), |source| {
+            source.start_draw(1);
+            let a = source.bits(8)?;
+            source.stop_draw();
+            source.start_draw(1);
+            let b = source.bits(8)?;
+            source.stop_draw();
+            if a >= 200 && a == b {
+                Ok(Status::Interesting(1))
+            } else if a >= 200 {
+                Ok(Status::Interesting(0))
+            } else {
+                Ok(Status::Valid)
+            }
+        });
+        let statistics = engine.statistics().unwrap();
+        assert!(statistics.phase(&Phase::Mutate).interesting > 0);
+        let results = engine.list_minimized_examples();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().any(|r| r.record == vec![200, 200]));
+    }
 }
@@ -0,0 +1,91 @@
+// Support for running the same strategies and tests that the runner
+// uses under a coverage-guided fuzzer such as cargo-fuzz, e.g.
+//
+//     fuzz_target!(|data: &[u8]| {
+//         run_fuzzer_input(data, &vecs(integers(), 0, 10), |v| check_sorting(v));
+//     });
+//
+// The fuzzer is good at finding crashes but not at explaining them,
+// so inputs that crash can be saved to the database with
+// save_fuzzer_crash. Running the test with a Runner that uses the
+// same name and database will then replay and shrink them.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::data::{u64s_to_bytes, DataSource};
+use crate::database::Database;
+use crate::runner::UnsatisfiedAssumption;
+use crate::strategy::{draw, Strategy};
+
+// Runs test on a value drawn from the fuzzer's input. Inputs that are
+// too short to draw a value from, or that are rejected by assume, are
+// ignored. Any other panic in the test is passed on to the fuzzer.
+pub fn run_fuzzer_input<S, F>(bytes: &[u8], strategy: &S, test: F)
+where
+    S: Strategy,
+    F: FnOnce(S::Value),
+{
+    let mut source = DataSource::from_fuzzer_bytes(bytes);
+    let value = match draw(&mut source, strategy) {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| test(value))) {
+        if !payload.is::<UnsatisfiedAssumption>() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+// Saves a fuzzer input as a failing example for the test called name.
+pub fn save_fuzzer_crash(database: &mut dyn Database, name: &str, bytes: &[u8]) {
+    let source = DataSource::from_fuzzer_bytes(bytes);
+    let choices = source.buffer().unwrap();
+    database.save(name, &u64s_to_bytes(choices));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DirectoryDatabase;
+    use crate::engine::Phase;
+    use crate::runner::{assume, Failure, Runner};
+    use crate::strategy::integers_up_to;
+
+    #[test]
+    fn ignores_inputs_that_are_too_short_or_rejected() {
+        run_fuzzer_input(&[], &integers_up_to(10), |_| panic!("Ran the test"));
+        run_fuzzer_input(&[1], &integers_up_to(10), |_| assume(false));
+    }
+
+    #[test]
+    #[should_panic(expected = "Found a bug")]
+    fn passes_on_failures() {
+        run_fuzzer_input(&[1], &integers_up_to(10), |_| panic!("Found a bug"));
+    }
+
+    #[test]
+    fn pads_partial_choices() {
+        let source = DataSource::from_fuzzer_bytes(&[0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(source.buffer().unwrap(), &[1, 2 << 56]);
+    }
+
+    #[test]
+    fn saved_crashes_are_replayed_and_shrunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let crash = [0, 0, 0, 0, 0, 0, 3, 32];
+        let test = |n: u64| assert!(n < 500);
+        run_fuzzer_input(&crash, &integers_up_to(1000), |n| assert_eq!(n, 800));
+
+        let mut database = DirectoryDatabase::new(dir.path());
+        save_fuzzer_crash(&mut database, "fuzzed", &crash);
+
+        let mut runner = Runner::new("fuzzed");
+        runner.settings.phases = vec![Phase::Reuse, Phase::Shrink];
+        runner.database = Box::new(DirectoryDatabase::new(dir.path()));
+        match runner.run(integers_up_to(1000), test) {
+            Err(Failure::Falsified(examples)) => assert_eq!(examples[0].value, "500"),
+            result => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+}
@@ -9,41 +9,41 @@ struct Minimizer<'a, F> {
 
 impl<'a, F, T> Minimizer<'a, F>
 where
-    F: 'a + FnMut(u64) -> Result<bool, T>,
+    F: 'a + AsyncFnMut(u64) -> Result<bool, T>,
 {
-    fn test(&mut self, candidate: u64) -> Result<bool, T> {
+    async fn test(&mut self, candidate: u64) -> Result<bool, T> {
         if candidate == self.best {
             return Ok(true);
         }
         if candidate > self.best {
             return Ok(false);
         }
-        let result = (self.criterion)(candidate)?;
+        let result = (self.criterion)(candidate).await?;
         if result {
             self.best = candidate;
         }
         Ok(result)
     }
 
-    fn modify<G>(&mut self, g: G) -> Result<bool, T>
+    async fn modify<G>(&mut self, g: G) -> Result<bool, T>
     where
         G: Fn(u64) -> u64,
     {
         let x = g(self.best);
-        self.test(x)
+        self.test(x).await
     }
 }
 
-pub fn minimize_integer<F, T>(start: u64, mut criterion: F) -> Result<u64, T>
+pub async fn minimize_integer<F, T>(start: u64, mut criterion: F) -> Result<u64, T>
 where
-    F: FnMut(u64) -> Result<bool, T>,
+    F: AsyncFnMut(u64) -> Result<bool, T>,
 {
     if start == 0 {
         return Ok(start);
     }
 
     for i in 0..min(start, SMALL) {
-        if criterion(i)? {
+        if criterion(i).await? {
             return Ok(i);
         }
     }
@@ -57,13 +57,13 @@ where
     };
 
     loop {
-        if !minimizer.modify(|x| x >> 1)? {
+        if !minimizer.modify(|x| x >> 1).await? {
             break;
         }
     }
 
     for i in 0..64 {
-        minimizer.modify(|x| x ^ (1 << i))?;
+        minimizer.modify(|x| x ^ (1 << i)).await?;
     }
 
     assert!(minimizer.best >= SMALL);
@@ -72,18 +72,20 @@ where
         let left_mask = 1 << i;
         let mut right_mask = left_mask >> 1;
         while right_mask != 0 {
-            minimizer.modify(|x| {
-                if x & left_mask == 0 || x & right_mask != 0 {
-                    x
-                } else {
-                    x ^ (right_mask | left_mask)
-                }
-            })?;
+            minimizer
+                .modify(|x| {
+                    if x & left_mask == 0 || x & right_mask != 0 {
+                        x
+                    } else {
+                        x ^ (right_mask | left_mask)
+                    }
+                })
+                .await?;
             right_mask >>= 1;
         }
     }
 
-    if !minimizer.modify(|x| x - 1)? {
+    if !minimizer.modify(|x| x - 1).await? {
         return Ok(minimizer.best);
     }
 
@@ -91,7 +93,7 @@ where
     let mut hi = minimizer.best;
     while lo + 1 < hi {
         let mid = lo + (hi - lo) / 2;
-        if minimizer.test(mid)? {
+        if minimizer.test(mid).await? {
             hi = mid;
         } else {
             lo = mid;
@@ -104,6 +106,18 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    // Our criteria never suspend, so a single poll runs the
+    // minimizer to completion.
+    fn run_now<R>(f: impl Future<Output = R>) -> R {
+        match pin!(f).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("Minimizer unexpectedly suspended"),
+        }
+    }
 
     fn non_failing_minimize<F>(start: u64, criterion: F) -> u64
     where
@@ -112,7 +126,7 @@ mod tests {
         let mut best = start;
 
         loop {
-            let ran: Result<u64, ()> = minimize_integer(best, |x| Ok(criterion(x)));
+            let ran: Result<u64, ()> = run_now(minimize_integer(best, async |x| Ok(criterion(x))));
             let result = ran.unwrap();
             assert!(result <= best);
             if result == best {
@@ -2,6 +2,12 @@
 #![warn(clippy::cargo, rust_2018_idioms, rust_2018_compatibility)]
 pub mod data;
 pub mod database;
+pub mod datatree;
 pub mod distributions;
 pub mod engine;
+pub mod fuzz;
 pub mod intminimize;
+pub mod mutator;
+pub mod runner;
+pub mod statistics;
+pub mod strategy;
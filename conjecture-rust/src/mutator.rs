@@ -0,0 +1,154 @@
+// Random mutations of choice sequences, in the style of a
+// coverage-guided fuzzer. These are used to explore the
+// neighbourhood of examples that we already know did something
+// interesting, in the hope of finding more examples that do.
+
+use rand::{ChaChaRng, Rng};
+
+use crate::data::{DataStream, DataStreamSlice, TestResult};
+
+// We apply up to this many mutations at once.
+const MAX_STACKED_MUTATIONS: usize = 4;
+
+// Returns a random modification of stream. Values that end up in
+// the result may be larger than the test will use, but that's fine
+// because DataSource masks them to the number of bits requested.
+pub fn mutate(
+    random: &mut ChaChaRng,
+    stream: &DataStreamSlice,
+    corpus: &[DataStream],
+) -> DataStream {
+    let mut result = stream.to_vec();
+    let n_mutations = random.gen_range(1, MAX_STACKED_MUTATIONS + 1);
+    for _ in 0..n_mutations {
+        mutate_once(random, &mut result, corpus);
+    }
+    result
+}
+
+fn mutate_once(random: &mut ChaChaRng, stream: &mut DataStream, corpus: &[DataStream]) {
+    if stream.is_empty() {
+        stream.push(random.next_u64());
+        return;
+    }
+    let i = random.gen_range(0, stream.len());
+    match random.gen_range(0, 6) {
+        // Replace a value with a completely random one.
+        0 => stream[i] = random.next_u64(),
+        // Nudge a value up or down a little.
+        1 => stream[i] = stream[i].wrapping_add(random.gen_range(1, 16)),
+        2 => stream[i] = stream[i].wrapping_sub(random.gen_range(1, 16)),
+        // Delete a range of values.
+        3 => {
+            let j = random.gen_range(i, stream.len()) + 1;
+            stream.drain(i..j);
+        }
+        // Copy a range of values to somewhere else in the stream.
+        4 => {
+            let j = random.gen_range(i, stream.len()) + 1;
+            let copied: DataStream = stream[i..j].to_vec();
+            let k = random.gen_range(0, stream.len() + 1);
+            stream.splice(k..k, copied);
+        }
+        // Replace the tail of the stream with the tail of another
+        // member of the corpus.
+        _ => {
+            if corpus.is_empty() {
+                return;
+            }
+            let other = &corpus[random.gen_range(0, corpus.len())];
+            let j = random.gen_range(0, other.len() + 1);
+            stream.truncate(i);
+            stream.extend_from_slice(&other[j..]);
+        }
+    }
+}
+
+// Returns a modification of target that changes one of its draws as a
+// whole, so that the result is still likely to be structurally similar
+// to it: We either replace the draw with a draw with the same label
+// from one of the donors, duplicate it, or replace it with fresh random
+// data. If target has no draws we fall back to mutate.
+pub fn mutate_draws(
+    random: &mut ChaChaRng,
+    target: &TestResult,
+    donors: &[TestResult],
+) -> DataStream {
+    if target.draws.is_empty() {
+        let corpus: Vec<DataStream> = donors.iter().map(|d| d.record.clone()).collect();
+        return mutate(random, &target.record, &corpus);
+    }
+    let draw = &target.draws[random.gen_range(0, target.draws.len())];
+    let span = draw.start..draw.end;
+    let mut result = target.record.clone();
+    match random.gen_range(0, 3) {
+        0 => {
+            let candidates: Vec<&[u64]> = donors
+                .iter()
+                .flat_map(|donor| {
+                    donor
+                        .draws
+                        .iter()
+                        .filter(|d| d.label == draw.label)
+                        .map(move |d| &donor.record[d.start..d.end])
+                })
+                .collect();
+            if !candidates.is_empty() {
+                let replacement = candidates[random.gen_range(0, candidates.len())];
+                result.splice(span, replacement.iter().copied());
+            }
+        }
+        1 => {
+            let copied: DataStream = result[span].to_vec();
+            result.splice(draw.end..draw.end, copied);
+        }
+        _ => {
+            for v in &mut result[span] {
+                *v = random.next_u64();
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataSource, Status};
+
+    #[test]
+    fn mutates_empty_streams() {
+        let mut random = ChaChaRng::new_unseeded();
+        for _ in 0..10 {
+            assert!(!mutate(&mut random, &[], &[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn produces_different_streams() {
+        let mut random = ChaChaRng::new_unseeded();
+        let stream = vec![1, 2, 3, 4];
+        let corpus = vec![vec![5, 6, 7]];
+        let changed = (0..100)
+            .filter(|_| mutate(&mut random, &stream, &corpus) != stream)
+            .count();
+        assert!(changed > 50);
+    }
+
+    #[test]
+    fn draw_mutations_keep_values_outside_the_draw() {
+        let mut random = ChaChaRng::new_unseeded();
+        let mut source = DataSource::from_vec(vec![1, 2, 3]);
+        source.bits(64).unwrap();
+        source.start_draw(0);
+        source.bits(64).unwrap();
+        source.stop_draw();
+        source.bits(64).unwrap();
+        let target = source.into_result(Status::Valid);
+        for _ in 0..20 {
+            let result = mutate_draws(&mut random, &target, std::slice::from_ref(&target));
+            assert_eq!(result[0], 1);
+            assert_eq!(result[result.len() - 1], 3);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+// Runs property-based tests written in Rust against the engine.
+//
+// A test is a function that takes a value drawn from a Strategy
+// and panics if the property does not hold for it. Each distinct
+// panic location is treated as a distinct reason for being
+// interesting, so the engine will try to shrink all of them.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use crate::data::{DataSource, Status};
+use crate::database::{BoxedDatabase, NoDatabase};
+use crate::engine::{Engine, HealthCheck, Settings};
+use crate::strategy::{draw, Strategy};
+
+// Panic payload used by assume to signal that the current
+// example should be discarded rather than treated as a failure.
+#[derive(Debug)]
+pub(crate) struct UnsatisfiedAssumption;
+
+// Discard the current example unless condition holds.
+pub fn assume(condition: bool) {
+    if !condition {
+        panic::resume_unwind(Box::new(UnsatisfiedAssumption));
+    }
+}
+
+thread_local! {
+    static CAPTURING_PANICS: Cell<bool> = const { Cell::new(false) };
+    static LAST_PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+// We run the test many times while generating and shrinking,
+// and having every one of those failures print a panic message
+// would bury the one that matters. We install a hook that stays
+// quiet (but remembers where the panic happened) while a test is
+// being run by us, and otherwise defers to whatever hook was
+// there before.
+fn install_panic_hook() {
+    INSTALL_PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CAPTURING_PANICS.with(Cell::get) {
+                let location = info
+                    .location()
+                    .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+                LAST_PANIC_LOCATION.with(|l| *l.borrow_mut() = location);
+            } else {
+                previous(info)
+            }
+        }));
+    });
+}
+
+enum Outcome {
+    Passed,
+    Overflow,
+    Rejected,
+    // The shown value (if we got as far as drawing one), the
+    // location of the panic and its message.
+    Failed(Option<String>, String, String),
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+fn run_once<S, F>(source: &mut DataSource, strategy: &S, test: &F) -> Outcome
+where
+    S: Strategy,
+    F: Fn(S::Value),
+{
+    CAPTURING_PANICS.with(|c| c.set(true));
+    LAST_PANIC_LOCATION.with(|l| *l.borrow_mut() = None);
+
+    let mut shown = None;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| match draw(source, strategy) {
+        Ok(value) => {
+            shown = Some(format!("{:?}", value));
+            test(value);
+            true
+        }
+        Err(_) => false,
+    }));
+
+    CAPTURING_PANICS.with(|c| c.set(false));
+
+    match result {
+        Ok(true) => Outcome::Passed,
+        Ok(false) => Outcome::Overflow,
+        Err(payload) => {
+            if payload.is::<UnsatisfiedAssumption>() {
+                Outcome::Rejected
+            } else {
+                let location = LAST_PANIC_LOCATION
+                    .with(|l| l.borrow_mut().take())
+                    .unwrap_or_else(|| "<unknown>".to_string());
+                Outcome::Failed(shown, location, panic_message(&*payload))
+            }
+        }
+    }
+}
+
+// A single shrunk failing example, as reported back to the user.
+#[derive(Debug, Clone)]
+pub struct FailingExample {
+    pub value: String,
+    pub location: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Failure {
+    // No example satisfied the test's assumptions.
+    Unsatisfiable,
+    // Generation was abandoned because of a problem with the
+    // strategy or the test, rather than with the property.
+    FailedHealthCheck(HealthCheck),
+    Falsified(Vec<FailingExample>),
+    // A failing example passed when it was replayed, so we can't
+    // report it.
+    Flaky,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Unsatisfiable => write!(f, "Unable to satisfy assumptions of property"),
+            Failure::FailedHealthCheck(check) => write!(f, "Failed health check: {}", check),
+            Failure::Falsified(examples) => {
+                if examples.len() > 1 {
+                    writeln!(f, "Found {} distinct failures.", examples.len())?;
+                }
+                for example in examples {
+                    writeln!(f)?;
+                    writeln!(f, "Falsifying example: {}", example.value)?;
+                    writeln!(f, "panicked at '{}', {}", example.message, example.location)?;
+                }
+                Ok(())
+            }
+            Failure::Flaky => write!(f, "Flaky test: Failing example did not fail when replayed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Runner {
+    pub name: String,
+    pub settings: Settings,
+    pub seed: Option<u64>,
+    pub database: BoxedDatabase,
+}
+
+impl Runner {
+    pub fn new(name: &str) -> Runner {
+        Runner {
+            name: name.to_string(),
+            settings: Settings::default(),
+            seed: None,
+            database: Box::new(NoDatabase),
+        }
+    }
+
+    pub fn run<S, F>(self, strategy: S, test: F) -> Result<(), Failure>
+    where
+        S: Strategy,
+        F: Fn(S::Value),
+    {
+        install_panic_hook();
+
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let xs: [u32; 2] = [seed as u32, (seed >> 32) as u32];
+        let max_choices = self.settings.max_choices;
+        let mut engine = Engine::new(self.name, self.settings, &xs, self.database);
+
+        let mut labels: HashMap<String, u64> = HashMap::new();
+
+        while let Some(mut source) = engine.next_source() {
+            let status = match run_once(&mut source, &strategy, &test) {
+                Outcome::Passed => Status::Valid,
+                Outcome::Overflow => Status::Overflow,
+                Outcome::Rejected => Status::Invalid,
+                Outcome::Failed(_, location, _) => {
+                    let n = labels.len() as u64;
+                    Status::Interesting(*labels.entry(location).or_insert(n))
+                }
+            };
+            engine.mark_finished(source, status);
+        }
+
+        let results = engine.list_minimized_examples();
+        if results.is_empty() {
+            if engine.was_unsatisfiable() {
+                return Err(Failure::Unsatisfiable);
+            }
+            if let Some(check) = engine.health_check_failure() {
+                return Err(Failure::FailedHealthCheck(check));
+            }
+            return Ok(());
+        }
+
+        let mut examples = Vec::new();
+        for result in results {
+            let mut source = DataSource::from_vec(result.record).with_max_size(max_choices);
+            match run_once(&mut source, &strategy, &test) {
+                Outcome::Failed(value, location, message) => examples.push(FailingExample {
+                    value: value.unwrap_or_default(),
+                    location,
+                    message,
+                }),
+                _ => return Err(Failure::Flaky),
+            }
+        }
+        Err(Failure::Falsified(examples))
+    }
+}
+
+// Checks that test passes for every value drawn from strategy,
+// panicking with a report of the shrunk failing examples if not.
+pub fn check<S, F>(name: &str, strategy: S, test: F)
+where
+    S: Strategy,
+    F: Fn(S::Value),
+{
+    if let Err(failure) = Runner::new(name).run(strategy, test) {
+        panic!("{}", failure);
+    }
+}
+
+// Defines a #[test] that checks a property over values drawn
+// from the given strategies, e.g.
+//
+//     property!(fn addition_commutes(x in integers(), y in integers()) {
+//         assert_eq!(x.wrapping_add(y), y.wrapping_add(x));
+//     });
+#[macro_export]
+macro_rules! property {
+    (fn $name:ident($($arg:ident in $strategy:expr),+ $(,)?) $body:block) => {
+        #[test]
+        fn $name() {
+            $crate::runner::check(
+                concat!(module_path!(), "::", stringify!($name)),
+                ($($strategy,)+),
+                |($($arg,)+)| $body,
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::Alphabet;
+    use crate::strategy::{
+        integers, integers_in_range, integers_up_to, recursive, text, vecs, Strategy,
+    };
+
+    #[derive(Debug)]
+    enum Tree {
+        Leaf,
+        Branch(Vec<Tree>),
+    }
+
+    impl Tree {
+        fn depth(&self) -> usize {
+            match self {
+                Tree::Leaf => 0,
+                Tree::Branch(children) => 1 + children.iter().map(Tree::depth).max().unwrap_or(0),
+            }
+        }
+    }
+
+    fn runner() -> Runner {
+        let mut runner = Runner::new("runner_tests");
+        runner.seed = Some(0);
+        runner
+    }
+
+    #[test]
+    fn passing_property_passes() {
+        check("passing_property_passes", integers_up_to(10), |n| {
+            assert!(n <= 10)
+        });
+    }
+
+    #[test]
+    fn shrinks_failing_property() {
+        let result = runner().run(integers(), |n| assert!(n < 1000));
+        match result {
+            Err(Failure::Falsified(examples)) => {
+                assert_eq!(examples.len(), 1);
+                assert_eq!(examples[0].value, "1000");
+            }
+            _ => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn shrinks_lists() {
+        let result = runner().run(vecs(integers_up_to(100), 0, 10), |v| {
+            assert!(v.iter().sum::<u64>() < 50)
+        });
+        match result {
+            Err(Failure::Falsified(examples)) => assert_eq!(examples[0].value, "[50]"),
+            _ => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn shrinks_negative_ranges_towards_zero() {
+        let result = runner().run(integers_in_range(-1000, -10), |n| assert!(n > -500));
+        match result {
+            Err(Failure::Falsified(examples)) => assert_eq!(examples[0].value, "-500"),
+            _ => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn shrinks_wide_ranges() {
+        let result = runner().run(integers_in_range(i128::MIN, i128::MAX), |n| {
+            assert!(n < 1 << 100)
+        });
+        match result {
+            Err(Failure::Falsified(examples)) => {
+                assert_eq!(examples[0].value, (1i128 << 100).to_string())
+            }
+            _ => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn shrinks_text() {
+        let alphabet = Alphabet::default();
+        let result = runner().run(text(&alphabet, 0, 10), |s| {
+            assert!(s.chars().all(|c| c.is_ascii_digit()))
+        });
+        match result {
+            Err(Failure::Falsified(examples)) => assert_eq!(examples[0].value, "\":\""),
+            _ => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn shrinks_recursive_values_towards_leaves() {
+        let trees = recursive(integers_up_to(10).map(|_| Tree::Leaf), 5, 50, |subtree| {
+            vecs(subtree, 0, 3).map(Tree::Branch)
+        });
+        let result = runner().run(trees, |t| assert!(t.depth() < 2));
+        match result {
+            Err(Failure::Falsified(examples)) => {
+                assert_eq!(examples[0].value, "Branch([Branch([])])")
+            }
+            _ => panic!("Expected a failure, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn limits_the_depth_of_recursive_values() {
+        let trees = recursive(integers_up_to(10).map(|_| Tree::Leaf), 3, 50, |subtree| {
+            vecs(subtree, 1, 3).map(Tree::Branch)
+        });
+        check("limits_the_depth_of_recursive_values", trees, |t| {
+            assert!(t.depth() <= 3)
+        });
+    }
+
+    #[test]
+    fn reports_unsatisfiable() {
+        let result = runner().run(integers(), |_| assume(false));
+        assert!(matches!(result, Err(Failure::Unsatisfiable)));
+    }
+
+    #[test]
+    fn reports_failed_health_checks() {
+        let result = runner().run(integers_up_to(9), |n| assume(n == 0));
+        assert!(matches!(
+            result,
+            Err(Failure::FailedHealthCheck(HealthCheck::FilterTooMuch))
+        ));
+    }
+
+    #[test]
+    fn reports_flaky_failures() {
+        let failed = Cell::new(false);
+        let result = runner().run(integers_up_to(100), |n| {
+            if n > 10 && !failed.get() {
+                failed.set(true);
+                panic!("Too big");
+            }
+        });
+        assert!(matches!(result, Err(Failure::Flaky)));
+    }
+
+    property!(fn macro_defines_test(x in integers_up_to(5), y in integers_up_to(5)) {
+        assert!(x + y <= 10);
+    });
+}
@@ -0,0 +1,71 @@
+// Information about what the engine did during a run, so that
+// users can find out why a test is slow or is not testing very
+// much.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::data::{Status, TestResult};
+use crate::engine::{LoopExitReason, Phase, ShrinkPass};
+
+// What happened while the engine was in a single phase. Counts
+// only include times the test was actually run, not results we
+// could look up because we had seen the same data before.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhaseStatistics {
+    pub valid: u64,
+    pub invalid: u64,
+    pub overflow: u64,
+    pub interesting: u64,
+    pub duration: Duration,
+    // The number of test executions in which each event was noted
+    // with DataSource::note_event.
+    pub events: HashMap<String, u64>,
+    // The number of draws made with each label, as passed to
+    // DataSource::start_draw.
+    pub draws_by_label: HashMap<u64, u64>,
+}
+
+impl PhaseStatistics {
+    pub fn calls(&self) -> u64 {
+        self.valid + self.invalid + self.overflow + self.interesting
+    }
+
+    pub(crate) fn record(&mut self, result: &TestResult) {
+        for event in &result.events {
+            *self.events.entry(event.clone()).or_insert(0) += 1;
+        }
+        for draw in &result.draws {
+            *self.draws_by_label.entry(draw.label).or_insert(0) += 1;
+        }
+        match result.status {
+            Status::Valid => self.valid += 1,
+            Status::Invalid => self.invalid += 1,
+            Status::Overflow => self.overflow += 1,
+            Status::Interesting(_) => self.interesting += 1,
+        }
+    }
+}
+
+// How useful a single shrink pass was. An attempt is any time the
+// pass asked for a test result, whether or not we already knew it,
+// and a success is an attempt that resulted in a smaller example.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShrinkPassStatistics {
+    pub attempts: u64,
+    pub successes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    pub phases: HashMap<Phase, PhaseStatistics>,
+    pub shrink_passes: HashMap<ShrinkPass, ShrinkPassStatistics>,
+    // Why the run ended. None if it is still going.
+    pub exit_reason: Option<LoopExitReason>,
+}
+
+impl Statistics {
+    pub fn phase(&self, phase: &Phase) -> PhaseStatistics {
+        self.phases.get(phase).cloned().unwrap_or_default()
+    }
+}
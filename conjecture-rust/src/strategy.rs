@@ -0,0 +1,377 @@
+// Strategies describe how to turn the choices made by a
+// DataSource into values of some Rust type. They are the
+// building blocks used by the runner module to express
+// property-based tests directly in Rust.
+
+use std::cell::{OnceCell, RefCell};
+use std::fmt::Debug;
+use std::rc::{Rc, Weak};
+
+use crate::data::{calc_label, DataSource, FailedDraw};
+use crate::distributions::{self, Repeat, Sampler};
+
+pub type Draw<T> = Result<T, FailedDraw>;
+
+pub trait Strategy {
+    type Value: Debug;
+
+    // Produce a single value from the source. Implementations
+    // should call draw for any nested strategies rather than
+    // calling draw_value on them directly, so that the resulting
+    // draws are properly recorded.
+    fn draw_value(&self, source: &mut DataSource) -> Draw<Self::Value>;
+
+    // Identifies draws made by this strategy. By default all strategies
+    // of the same type share a label.
+    fn label(&self) -> u64 {
+        calc_label(std::any::type_name::<Self>())
+    }
+
+    fn map<F, T>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: Fn(Self::Value) -> T,
+        T: Debug,
+    {
+        Map { base: self, f }
+    }
+}
+
+// Draws a value from a strategy, marking the choices it made
+// as a single draw so that the shrinker can treat them as a unit.
+pub fn draw<S>(source: &mut DataSource, strategy: &S) -> Draw<S::Value>
+where
+    S: Strategy + ?Sized,
+{
+    source.start_draw(strategy.label());
+    let result = strategy.draw_value(source)?;
+    source.stop_draw();
+    Ok(result)
+}
+
+impl<S> Strategy for Box<S>
+where
+    S: Strategy + ?Sized,
+{
+    type Value = S::Value;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<S::Value> {
+        (**self).draw_value(source)
+    }
+
+    fn label(&self) -> u64 {
+        (**self).label()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Map<S, F> {
+    base: S,
+    f: F,
+}
+
+impl<S, F, T> Strategy for Map<S, F>
+where
+    S: Strategy,
+    F: Fn(S::Value) -> T,
+    T: Debug,
+{
+    type Value = T;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<T> {
+        let value = draw(source, &self.base)?;
+        Ok((self.f)(value))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Just<T> {
+    value: T,
+}
+
+pub fn just<T: Clone + Debug>(value: T) -> Just<T> {
+    Just { value }
+}
+
+impl<T: Clone + Debug> Strategy for Just<T> {
+    type Value = T;
+
+    fn draw_value(&self, _source: &mut DataSource) -> Draw<T> {
+        Ok(self.value.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Booleans;
+
+pub fn booleans() -> Booleans {
+    Booleans
+}
+
+impl Strategy for Booleans {
+    type Value = bool;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<bool> {
+        distributions::weighted(source, 0.5)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Integers {
+    bitlengths: Sampler,
+}
+
+pub fn integers() -> Integers {
+    Integers {
+        bitlengths: distributions::good_bitlengths(),
+    }
+}
+
+impl Strategy for Integers {
+    type Value = i64;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<i64> {
+        distributions::integer_from_bitlengths(source, &self.bitlengths)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BoundedIntegers {
+    max_value: u64,
+}
+
+// Unsigned integers in the range 0..=max_value, shrinking towards 0.
+pub fn integers_up_to(max_value: u64) -> BoundedIntegers {
+    BoundedIntegers { max_value }
+}
+
+impl Strategy for BoundedIntegers {
+    type Value = u64;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<u64> {
+        distributions::bounded_int(source, self.max_value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntegersInRange {
+    lo: i128,
+    hi: i128,
+}
+
+// Integers in the range lo..=hi, shrinking towards zero or, if zero
+// is not in the range, towards the end of it closest to zero.
+pub fn integers_in_range(lo: i128, hi: i128) -> IntegersInRange {
+    assert!(lo <= hi);
+    IntegersInRange { lo, hi }
+}
+
+impl Strategy for IntegersInRange {
+    type Value = i128;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<i128> {
+        distributions::integers_in_range(source, self.lo, self.hi)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Text {
+    characters: distributions::Characters,
+    min_size: u64,
+    max_size: u64,
+}
+
+// Strings of between min_size and max_size characters from alphabet,
+// shrinking towards strings of '0's.
+pub fn text(alphabet: &distributions::Alphabet, min_size: u64, max_size: u64) -> Text {
+    assert!(min_size <= max_size);
+    Text {
+        characters: distributions::Characters::new(alphabet).unwrap(),
+        min_size,
+        max_size,
+    }
+}
+
+impl Strategy for Text {
+    type Value = String;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<String> {
+        distributions::text(source, &self.characters, self.min_size, self.max_size)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Binary {
+    min_size: u64,
+    max_size: u64,
+}
+
+// Byte strings of between min_size and max_size bytes.
+pub fn binary(min_size: u64, max_size: u64) -> Binary {
+    assert!(min_size <= max_size);
+    Binary { min_size, max_size }
+}
+
+impl Strategy for Binary {
+    type Value = Vec<u8>;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<Vec<u8>> {
+        distributions::binary(source, self.min_size, self.max_size)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SampledFrom<T> {
+    values: Vec<T>,
+}
+
+// Picks uniformly from a non-empty list of values, shrinking
+// towards the first one.
+pub fn sampled_from<T: Clone + Debug>(values: Vec<T>) -> SampledFrom<T> {
+    assert!(!values.is_empty(), "Cannot sample from an empty list");
+    SampledFrom { values }
+}
+
+impl<T: Clone + Debug> Strategy for SampledFrom<T> {
+    type Value = T;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<T> {
+        let i = distributions::bounded_int(source, self.values.len() as u64 - 1)?;
+        Ok(self.values[i as usize].clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vecs<S> {
+    elements: S,
+    min_size: u64,
+    max_size: u64,
+}
+
+pub fn vecs<S: Strategy>(elements: S, min_size: u64, max_size: u64) -> Vecs<S> {
+    assert!(min_size <= max_size);
+    Vecs {
+        elements,
+        min_size,
+        max_size,
+    }
+}
+
+impl<S: Strategy> Strategy for Vecs<S> {
+    type Value = Vec<S::Value>;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<Vec<S::Value>> {
+        let mut repeat = Repeat::new(
+            self.min_size,
+            self.max_size,
+            (self.min_size + self.max_size) as f64 * 0.5,
+        );
+        let mut result = Vec::new();
+        while repeat.should_continue(source)? {
+            result.push(draw(source, &self.elements)?);
+        }
+        Ok(result)
+    }
+}
+
+struct RecursiveNode<T> {
+    leaf: Box<dyn Strategy<Value = T>>,
+    // Only empty while the strategy is being built.
+    branch: OnceCell<Box<dyn Strategy<Value = T>>>,
+    budget: RefCell<distributions::Recursive>,
+}
+
+impl<T: Debug> RecursiveNode<T> {
+    fn draw_node(&self, source: &mut DataSource) -> Draw<T> {
+        if self.budget.borrow_mut().start_node(source)? {
+            let result = draw(source, self.branch.get().unwrap())?;
+            self.budget.borrow_mut().stop_branch(source);
+            Ok(result)
+        } else {
+            draw(source, &self.leaf)
+        }
+    }
+}
+
+// Draws a node of the recursive strategy it was passed to the extend
+// function of.
+pub struct Subtree<T> {
+    node: Weak<RecursiveNode<T>>,
+}
+
+impl<T: Debug> Strategy for Subtree<T> {
+    type Value = T;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<T> {
+        self.node.upgrade().unwrap().draw_node(source)
+    }
+}
+
+pub struct RecursiveStrategy<T> {
+    node: Rc<RecursiveNode<T>>,
+    max_depth: u64,
+    max_nodes: u64,
+}
+
+// Draws recursive values such as trees. Each node is either drawn from
+// leaf, or is a branch drawn from the strategy that extend returns
+// when given a strategy for subtrees. Values shrink towards leaves.
+// Branches are at most max_depth deep, and values with more than
+// max_nodes nodes overflow.
+pub fn recursive<L, F, S>(
+    leaf: L,
+    max_depth: u64,
+    max_nodes: u64,
+    extend: F,
+) -> RecursiveStrategy<L::Value>
+where
+    L: Strategy + 'static,
+    F: FnOnce(Subtree<L::Value>) -> S,
+    S: Strategy<Value = L::Value> + 'static,
+{
+    let node = Rc::new(RecursiveNode {
+        leaf: Box::new(leaf),
+        branch: OnceCell::new(),
+        budget: RefCell::new(distributions::Recursive::new(max_depth, max_nodes)),
+    });
+    let branch = extend(Subtree {
+        node: Rc::downgrade(&node),
+    });
+    let _ = node.branch.set(Box::new(branch));
+    RecursiveStrategy {
+        node,
+        max_depth,
+        max_nodes,
+    }
+}
+
+impl<T: Debug> Strategy for RecursiveStrategy<T> {
+    type Value = T;
+
+    fn draw_value(&self, source: &mut DataSource) -> Draw<T> {
+        *self.node.budget.borrow_mut() =
+            distributions::Recursive::new(self.max_depth, self.max_nodes);
+        self.node.draw_node(source)
+    }
+}
+
+macro_rules! tuple_strategy {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Strategy),+> Strategy for ($($name,)+) {
+            type Value = ($($name::Value,)+);
+
+            fn draw_value(&self, source: &mut DataSource) -> Draw<Self::Value> {
+                let ($(ref $name,)+) = *self;
+                Ok(($(draw(source, $name)?,)+))
+            }
+        }
+    };
+}
+
+tuple_strategy!(A);
+tuple_strategy!(A, B);
+tuple_strategy!(A, B, C);
+tuple_strategy!(A, B, C, D);
+tuple_strategy!(A, B, C, D, E);
+tuple_strategy!(A, B, C, D, E, F);
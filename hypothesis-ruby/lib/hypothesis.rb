@@ -8,12 +8,17 @@ require_relative 'hypothesis/engine'
 require_relative 'hypothesis/world'
 
 module Phase
+  EXPLICIT = :explicit
+  REUSE = :reuse
+  GENERATE = :generate
+  TARGET = :target
+  MUTATE = :mutate
   SHRINK = :shrink
 
   module_function
 
   def all
-    [SHRINK]
+    [EXPLICIT, REUSE, GENERATE, TARGET, MUTATE, SHRINK]
   end
 
   def excluding(*phases)
@@ -29,6 +34,19 @@ module Phase
   end
 end
 
+module HealthCheck
+  FILTER_TOO_MUCH = :filter_too_much
+  TOO_MANY_OVERFLOWS = :too_many_overflows
+  DATA_TOO_LARGE = :data_too_large
+  TOO_SLOW = :too_slow
+
+  module_function
+
+  def all
+    [FILTER_TOO_MUCH, TOO_MANY_OVERFLOWS, DATA_TOO_LARGE, TOO_SLOW]
+  end
+end
+
 # This is the main module for using Hypothesis.
 # It is expected that you will include this in your
 # tests, but its methods are also available on the
@@ -195,7 +213,7 @@ module Hypothesis
   # up automatically.
   #
   # Generation consists of randomly trying test cases until one of
-  # three things has happened:
+  # four things has happened:
   #
   # 1. It has found a failing test case. At this point it will start
   #    *shrinking* the test case (see below).
@@ -206,6 +224,26 @@ module Hypothesis
   #    time. At this point it will either silently stop or raise
   #    `Hypothesis::Unsatisfiable` depending on how many valid
   #    examples it found.
+  # 4. It has tried every possible test case. This can only happen
+  #    when there are few enough possible values (e.g. a handful of
+  #    booleans). At this point it will silently stop.
+  #
+  # If the test calls {Hypothesis#target} and generation did not find a
+  # failing test case, Hypothesis will then try to *target* the scores it
+  # was given, by changing the best test cases it has seen so far to try
+  # to increase their scores.
+  #
+  # Before shrinking, Hypothesis will *mutate* the failing test cases
+  # (and the test cases with the best scores) it has found so far, making
+  # changes to individual givens in them in the hope of finding other
+  # failing test cases nearby.
+  #
+  # While generating the first few test cases Hypothesis also runs
+  # some *health checks*, and raises `Hypothesis::FailedHealthCheck`
+  # if it looks like generation is not going to work well: If most
+  # test cases are being rejected by assume, if the test cases
+  # routinely run out of data or are very large, or if they are
+  # very slow.
   #
   # *Shrinking* is when Hypothesis takes a failing test case and tries
   # to make it easier to understand. It does this by replacing the givens
@@ -219,14 +257,25 @@ module Hypothesis
   # @param max_valid_test_cases [Integer] The maximum number of valid test
   #   cases to run without finding a failing test case before stopping.
   #
+  # @param phases [Array<Symbol>] The phases of a run that Hypothesis should
+  #   perform. Defaults to all of them, but e.g.
+  #   `Phase.excluding(:generate)` will only replay test cases from the
+  #   database and `Phase.excluding(:shrink)` will skip shrinking.
+  #
   # @param database [String, nil, false] A path to a directory where Hypothesis
   #   should store previously failing test cases. If it is nil, Hypothesis
   #   will use a default of .hypothesis/examples in the current directory.
   #   May also be set to false to disable the database functionality.
+  #
+  # @param suppress_health_check [Array<Symbol>] Health checks that should
+  #   not raise an error when they fail, e.g. `[HealthCheck::TOO_SLOW]` for
+  #   a test that is expected to be slow. `HealthCheck.all` disables all of
+  #   them.
   def hypothesis(
     max_valid_test_cases: 200,
     phases: Phase.all,
     database: nil,
+    suppress_health_check: [],
     &block
   )
     unless World.current_engine.nil?
@@ -238,7 +287,8 @@ module Hypothesis
         hypothesis_stable_identifier,
         max_examples: max_valid_test_cases,
         phases: phases,
-        database: database
+        database: database,
+        suppress_health_check: suppress_health_check
       )
       World.current_engine.run(&block)
     ensure
@@ -283,4 +333,40 @@ module Hypothesis
     end
     World.current_engine.current_source.assume(condition)
   end
+
+  # Asks Hypothesis to look for test cases that make score as large as
+  # possible. After generating test cases as normal, Hypothesis will
+  # make small changes to the test cases with the highest scores to try
+  # to increase them further. This is useful for finding e.g. the inputs
+  # that make your code slowest or use the most memory, which are good
+  # places to look for bugs.
+  # @note It is invalid to call this method outside of a hypothesis block.
+  # @param score [Numeric] The score for the current test case. Scores
+  #   that are NaN or infinite are ignored.
+  # @param label [String] Distinguishes between different scores in the
+  #   same test, each of which Hypothesis will try to maximize
+  #   independently. Calling target twice with the same label in one test
+  #   case replaces the earlier score.
+  def target(score, label: '')
+    if World.current_engine.nil?
+      raise UsageError, 'Cannot call target outside of a hypothesis block'
+    end
+    World.current_engine.current_source.target(score, label)
+  end
+
+  # Records that something happened in the current test case. At the end
+  # of a run Hypothesis counts how many test cases each event happened in,
+  # which can be seen in {Hypothesis::Engine#statistics}. This is useful
+  # for checking that your tests are actually exercising the edge cases
+  # you care about, e.g. `event('empty list') if ls.empty?`.
+  # @note It is invalid to call this method outside of a hypothesis block.
+  # @param description [String] A description of what happened. Test cases
+  #   are counted by exact equality of descriptions, so you should avoid
+  #   including generated values in them.
+  def event(description)
+    if World.current_engine.nil?
+      raise UsageError, 'Cannot call event outside of a hypothesis block'
+    end
+    World.current_engine.current_source.event(description)
+  end
 end
@@ -23,12 +23,22 @@ module Hypothesis
         database,
         seed,
         options.fetch(:max_examples),
-        options.fetch(:phases)
+        options.fetch(:phases),
+        options.fetch(:suppress_health_check, [])
       )
 
       @exceptions_to_tags = Hash.new { |h, k| h[k] = h.size }
     end
 
+    # A Hash describing what happened during the run: How many test
+    # cases of each kind ran in each phase and how long each phase
+    # took, how many attempts each shrink pass made and how many of
+    # them succeeded, and why the run stopped. nil until the run is
+    # finished.
+    def statistics
+      @core_engine.statistics
+    end
+
     def run
       loop do
         core = @core_engine.new_source
@@ -56,6 +66,8 @@ module Hypothesis
       end
       if @core_engine.count_failing_examples.zero?
         raise Unsatisfiable if @core_engine.was_unsatisfiable
+        health_check = @core_engine.health_check_failure
+        raise FailedHealthCheck, health_check unless health_check.nil?
         @current_source = nil
         return
       end
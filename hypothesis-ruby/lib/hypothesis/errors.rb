@@ -13,6 +13,15 @@ module Hypothesis
   class Unsatisfiable < HypothesisError
   end
 
+  # Indicates that Hypothesis gave up on generating test
+  # cases early because of a problem with how they are being
+  # generated, e.g. because almost all of them were rejected
+  # by assume or because they are very slow. Individual checks
+  # can be disabled with the suppress_health_check argument to
+  # {Hypothesis#hypothesis}.
+  class FailedHealthCheck < HypothesisError
+  end
+
   # Indicates that the Hypothesis API has been used
   # incorrectly in some manner.
   class UsageError < HypothesisError
@@ -1,5 +1,7 @@
 # frozen_string_literal: true
 
+require 'zlib'
+
 # @!visibility private
 class HypothesisCoreRepeatValues
   def should_continue(source)
@@ -9,6 +11,19 @@ class HypothesisCoreRepeatValues
   end
 end
 
+# @!visibility private
+class HypothesisCoreRecursive
+  def start_node(source)
+    result = _start_node(source.wrapped_data)
+    raise Hypothesis::DataOverflow if result.nil?
+    result
+  end
+
+  def stop_branch(source)
+    _stop_branch(source.wrapped_data)
+  end
+end
+
 module Hypothesis
   class <<self
     include Hypothesis
@@ -68,6 +83,13 @@ module Hypothesis
 
     alias filter select
 
+    # @!visibility private
+    # Identifies the values drawn from this Possible, so that Hypothesis
+    # can tell which parts of a test case are the same kind of thing.
+    def hypothesis_label
+      @hypothesis_label ||= Zlib.crc32(hypothesis_label_name)
+    end
+
     # @!visibility private
     module Implementations
       # @!visibility private
@@ -80,6 +102,11 @@ module Hypothesis
         def provide(&block)
           (@block || block).call
         end
+
+        # @!visibility private
+        def hypothesis_label_name
+          @block.source_location.join(':')
+        end
       end
 
       # @!visibility private
@@ -95,6 +122,11 @@ module Hypothesis
           raise Hypothesis::DataOverflow if result.nil?
           result
         end
+
+        # @!visibility private
+        def hypothesis_label_name
+          @core_possible.class.name
+        end
       end
     end
   end
@@ -154,17 +186,26 @@ module Hypothesis
 
     alias boolean booleans
 
-    # A Possible unicode codepoint.
+    # A Possible unicode codepoint. Codepoints shrink towards '0',
+    # then towards other ASCII characters.
     # @return [Possible]
     # @param min [Integer] The smallest codepoint to provide
     # @param max [Integer] The largest codepoint to provide
-    def codepoints(min: 1, max: 1_114_111)
-      base = integers(min: min, max: max)
-      if min <= 126
-        from(integers(min: min, max: [126, max].min), base)
-      else
-        base
-      end
+    # @param categories [Array<Symbol>, nil] If not nil, only provide
+    #   codepoints in one of these categories. Valid categories are
+    #   :control, :whitespace, :uppercase, :lowercase, :other_letter,
+    #   :number, :punctuation and :other.
+    # @param exclude_categories [Array<Symbol>] Never provide
+    #   codepoints in any of these categories.
+    # @param exclude_characters [String] Never provide any of the
+    #   characters in this string.
+    def codepoints(
+      min: 1, max: 1_114_111, categories: nil,
+      exclude_categories: [], exclude_characters: ''
+    )
+      from_hypothesis_core HypothesisCoreCharacters.new(
+        min, max, categories, exclude_categories, exclude_characters
+      )
     end
 
     alias codepoint codepoints
@@ -172,16 +213,26 @@ module Hypothesis
     # A Possible String
     # @return [Possible]
     # @param codepoints [Possible, nil] The Possible codepoints
-    #   that can be found in the string. If nil,
-    #   will default to self.codepoints. These
+    #   that can be found in the string. If nil, the string is
+    #   generated natively from the codepoint options below, which
+    #   are as for self.codepoints. Otherwise these
     #   will be further filtered to ensure the generated string is
     #   valid.
     # @param min_size [Integer] The smallest valid length for a
     #   provided string
     # @param max_size [Integer] The largest valid length for a
     #   provided string
-    def strings(codepoints: nil, min_size: 0, max_size: 10)
-      codepoints = self.codepoints if codepoints.nil?
+    def strings(
+      codepoints: nil, min_size: 0, max_size: 10,
+      min_codepoint: 1, max_codepoint: 1_114_111, categories: nil,
+      exclude_categories: [], exclude_characters: ''
+    )
+      if codepoints.nil?
+        return from_hypothesis_core HypothesisCoreText.new(
+          min_codepoint, max_codepoint, categories,
+          exclude_categories, exclude_characters, min_size, max_size
+        )
+      end
       codepoints = codepoints.select do |i|
         begin
           [i].pack('U*').codepoints
@@ -197,6 +248,19 @@ module Hypothesis
 
     alias string strings
 
+    # A Possible binary String, i.e. one with ASCII-8BIT encoding.
+    # Its bytes shrink towards zero.
+    # @return [Possible]
+    # @param min_size [Integer] The smallest valid length for a
+    #   provided string
+    # @param max_size [Integer] The largest valid length for a
+    #   provided string
+    def binaries(min_size: 0, max_size: 10)
+      from_hypothesis_core HypothesisCoreBinary.new(min_size, max_size)
+    end
+
+    alias binary binaries
+
     # A Possible Hash, where all possible values have a fixed
     # shape.
     # This is used for hashes where you know exactly what the
@@ -299,11 +363,13 @@ module Hypothesis
     #   them. If components contains an
     #   array it will be flattened first, so e.g. from(a, b)
     #   is equivalent to from([a, b])
-    def from(*components)
+    # @param weights [Array<Numeric>, nil] If given, how often to pick
+    #   each of the components, relative to the others. Components with
+    #   zero weight are never picked. If nil, every component is
+    #   equally likely.
+    def from(*components, weights: nil)
       components = components.flatten
-      indexes = from_hypothesis_core(
-        HypothesisCoreBoundedIntegers.new(components.size - 1)
-      )
+      indexes = choice_indexes(components.size, weights)
       built_as do
         i = any indexes
         any components[i]
@@ -312,6 +378,46 @@ module Hypothesis
 
     alias mix_of from
 
+    # A Possible recursive value, such as a tree. Each provided value is
+    # either a leaf, provided by base, or a branch, provided by the
+    # Possible that the block returns when passed a Possible for the
+    # children of the branch. Values shrink towards leaves.
+    # For example, the following provides integers and arbitrarily
+    # nested arrays of them:
+    #
+    # ```ruby
+    #   recursive(integers) { |children| arrays(of: children) }
+    # ```
+    #
+    # @return [Possible]
+    # @param base [Possible] The possible leaves.
+    # @param max_depth [Integer] How deeply branches may be nested.
+    #   Children of branches at this depth are always leaves.
+    # @param max_nodes [Integer] The largest number of branches and
+    #   leaves that a provided value may have. Branches get less likely
+    #   as a value gets close to this, and test cases that exceed it
+    #   are discarded.
+    # @yield [Possible] A Possible for the children of a branch.
+    def recursive(base, max_depth: 5, max_nodes: 100)
+      budget = nil
+      node = nil
+      branch = yield(built_as { any node })
+      node = built_as do
+        source = World.current_engine.current_source
+        if budget.start_node(source)
+          result = any branch
+          budget.stop_branch(source)
+          result
+        else
+          any base
+        end
+      end
+      built_as do
+        budget = HypothesisCoreRecursive.new(max_depth, max_nodes)
+        any node
+      end
+    end
+
     # A Possible where any one of a fixed array of values is possible.
     # @note these values are provided as is, so if the provided
     #   values are mutated in the test you should be careful to make
@@ -321,11 +427,16 @@ module Hypothesis
     #   hypothesis block).
     # @return [Possible]
     # @param values [Enumerable] A collection of possible values.
-    def element_of(values)
+    #   If this is empty, any test case that draws from it is rejected,
+    #   as if by {Hypothesis#assume}.
+    # @param weights [Array<Numeric>, nil] If given, how often to provide
+    #   each of the values, relative to the others, as for {#from}.
+    #   Either way, provided values shrink towards the first one.
+    def element_of(values, weights: nil)
       values = values.to_a
-      indexes = from_hypothesis_core(
-        HypothesisCoreBoundedIntegers.new(values.size - 1)
-      )
+      return built_as { assume(false) } if values.empty? && weights.nil?
+
+      indexes = choice_indexes(values.size, weights)
       built_as do
         values.fetch(any(indexes))
       end
@@ -333,7 +444,9 @@ module Hypothesis
 
     alias elements_of element_of
 
-    # A Possible integer
+    # A Possible integer. When both bounds are given, integers shrink
+    # towards zero, or towards whichever bound is closest to zero if
+    # zero is not allowed.
     # @return [Possible]
     # @param min [Integer] The smallest value integer to provide.
     # @param max [Integer] The largest value integer to provide.
@@ -346,19 +459,33 @@ module Hypothesis
       elsif max.nil?
         built_as { min + any(base).abs }
       else
-        bounded = from_hypothesis_core(
-          HypothesisCoreBoundedIntegers.new(max - min)
-        )
-        if min.zero?
-          bounded
-        else
-          built_as { min + any(bounded) }
-        end
+        from_hypothesis_core HypothesisCoreIntegersInRange.new(min, max)
       end
     end
 
     alias integer integers
 
+    # A Possible float
+    # @return [Possible]
+    # @param min [Float] The smallest float to provide, or nil for no
+    #   lower bound.
+    # @param max [Float] The largest float to provide, or nil for no
+    #   upper bound.
+    # @param allow_nan [Boolean] Whether NaN is possible. Defaults to
+    #   true if neither bound is given.
+    # @param allow_infinity [Boolean] Whether infinite floats are
+    #   possible. Defaults to true unless both bounds are given.
+    def floats(min: nil, max: nil, allow_nan: nil, allow_infinity: nil)
+      allow_nan = min.nil? && max.nil? if allow_nan.nil?
+      allow_infinity = min.nil? || max.nil? if allow_infinity.nil?
+      from_hypothesis_core HypothesisCoreFloats.new(
+        (min || -Float::INFINITY).to_f, (max || Float::INFINITY).to_f,
+        allow_nan, allow_infinity
+      )
+    end
+
+    alias float floats
+
     private
 
     def from_hypothesis_core(core)
@@ -366,5 +493,18 @@ module Hypothesis
         core
       )
     end
+
+    def choice_indexes(size, weights)
+      if weights.nil?
+        return from_hypothesis_core(
+          HypothesisCoreBoundedIntegers.new(size - 1)
+        )
+      end
+      unless weights.size == size
+        raise ArgumentError, "Expected #{size} weights but got #{weights.size}"
+      end
+
+      from_hypothesis_core HypothesisCoreSampler.new(weights.map(&:to_f))
+    end
   end
 end
@@ -23,6 +23,14 @@ module Hypothesis
       raise UnsatisfiedAssumption unless condition
     end
 
+    def target(score, label)
+      @wrapped_data.target(label.to_s, score.to_f)
+    end
+
+    def event(description)
+      @wrapped_data.note_event(description.to_s)
+    end
+
     # @!visibility private
     def any(possible = nil, name: nil, &block)
       top_level = @depth.zero?
@@ -30,7 +38,7 @@ module Hypothesis
       begin
         @depth += 1
         possible ||= block
-        @wrapped_data.start_draw
+        @wrapped_data.start_draw(possible.hypothesis_label)
         result = possible.provide(&block)
         @wrapped_data.stop_draw
         if top_level
@@ -22,6 +22,14 @@ RSpec.describe 'Incorrect usage' do
     bad_usage { assume true }
   end
 
+  it 'includes using target outside a hypothesis call' do
+    bad_usage { target 1 }
+  end
+
+  it 'includes using event outside a hypothesis call' do
+    bad_usage { event 'happened' }
+  end
+
   it 'includes using find inside a hypothesis' do
     class <<self
       include Hypothesis::Debug
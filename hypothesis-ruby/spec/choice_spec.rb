@@ -17,3 +17,51 @@ RSpec.describe 'element_of possible' do
     end
   end
 end
+
+RSpec.describe 'element_of an empty collection' do
+  it 'rejects the test case' do
+    hypothesis do
+      if any(booleans)
+        any element_of([])
+        raise 'Drew from an empty collection'
+      end
+    end
+  end
+end
+
+RSpec.describe 'weighted choices' do
+  include Hypothesis::Debug
+
+  it 'never provide values with zero weight' do
+    hypothesis do
+      expect(any(element_of(%i[a b c], weights: [1, 0, 2]))).to_not eq(:b)
+    end
+  end
+
+  it 'can provide rare values' do
+    find_any { any(element_of(%i[a b], weights: [100, 1])) == :b }
+  end
+
+  it 'shrink towards the first value' do
+    m, = find { any(element_of([0, 1, 2], weights: [1, 2, 3])) }
+    expect(m).to eq(0)
+  end
+
+  it 'can pick between possible values' do
+    find_any do
+      any(from(integers(min: 0, max: 0), booleans, weights: [0.1, 10])) == 0
+    end
+  end
+
+  it 'reject the wrong number of weights' do
+    expect { element_of([1, 2], weights: [1]) }.to raise_exception(
+      ArgumentError
+    )
+  end
+
+  it 'reject negative weights' do
+    expect { element_of([1, 2], weights: [1, -1]) }.to raise_exception(
+      ArgumentError
+    )
+  end
+end
@@ -60,4 +60,20 @@ RSpec.describe 'shrinking' do
       expect(ls).to eq([1, 1])
     end
   end
+
+  it 'sorts the elements of arrays' do
+    ls, = find do
+      x = any arrays(of: integers(min: 0, max: 1000))
+      x.length == 2 && x.max >= 500
+    end
+    expect(ls).to eq([0, 500])
+  end
+
+  it 'moves value between elements of arrays' do
+    ls, = find do
+      x = any arrays(of: integers(min: 0, max: 1000))
+      x.length == 2 && x.sum > 1000
+    end
+    expect(ls).to eq([1, 1000])
+  end
 end
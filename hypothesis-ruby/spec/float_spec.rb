@@ -0,0 +1,42 @@
+# frozen_string_literal: true
+
+RSpec.describe 'float possibles' do
+  include Hypothesis::Debug
+
+  they 'respect both bounds at once' do
+    hypothesis do
+      f = any floats(min: -1.5, max: 10)
+      expect(f).to be >= -1.5
+      expect(f).to be <= 10
+    end
+  end
+
+  they 'are finite when not allowed infinity or nan' do
+    hypothesis do
+      f = any floats(allow_nan: false, allow_infinity: false)
+      expect(f).to be_finite
+    end
+  end
+
+  they 'are not nan when bounded' do
+    hypothesis do
+      expect(any(floats(min: 0))).to_not be_nan
+    end
+  end
+
+  they 'shrink to simple fractions' do
+    f, = find do
+      f = any floats(min: 0)
+      f > 1.2 && f != f.floor
+    end
+    expect(f).to eq(1.5)
+  end
+
+  they 'reject empty ranges' do
+    expect { floats(min: 1, max: 0) }.to raise_exception(ArgumentError)
+    expect { floats(min: Float::NAN) }.to raise_exception(ArgumentError)
+    expect do
+      floats(min: Float::INFINITY, allow_infinity: false)
+    end.to raise_exception(ArgumentError)
+  end
+end
@@ -0,0 +1,33 @@
+# frozen_string_literal: true
+
+RSpec.describe 'health checks' do
+  it 'fail when a test is too slow' do
+    expect do
+      hypothesis do
+        any integers
+        sleep 0.2
+      end
+    end.to raise_exception(Hypothesis::FailedHealthCheck)
+  end
+
+  it 'fail when most test cases are filtered out' do
+    calls = 0
+    expect do
+      hypothesis do
+        any integers
+        calls += 1
+        assume calls == 1
+      end
+    end.to raise_exception(Hypothesis::FailedHealthCheck)
+  end
+
+  it 'can be suppressed' do
+    hypothesis(
+      max_valid_test_cases: 10,
+      suppress_health_check: [HealthCheck::TOO_SLOW]
+    ) do
+      any integers
+      sleep 0.11
+    end
+  end
+end
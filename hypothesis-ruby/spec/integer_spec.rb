@@ -28,4 +28,30 @@ RSpec.describe 'integer possibles' do
       expect(n).to be >= 1
     end
   end
+
+  they 'respect bounds that do not fit in 64 bits' do
+    hypothesis do
+      n = any integers(min: -2**100, max: 2**100)
+      expect(n).to be <= 2**100
+      expect(n).to be >= -2**100
+    end
+  end
+
+  they 'reject empty ranges' do
+    expect { integers(min: 5, max: 1) }.to raise_exception(ArgumentError)
+  end
+
+  describe 'shrinking' do
+    include Hypothesis::Debug
+
+    they 'shrink bounded negative ranges towards zero' do
+      n, = find { any(integers(min: -1000, max: -10)) <= -500 }
+      expect(n).to eq(-500)
+    end
+
+    they 'shrink towards zero when it is in range' do
+      n, = find { any(integers(min: -1000, max: 1000)).abs >= 100 }
+      expect(n).to eq(100)
+    end
+  end
 end
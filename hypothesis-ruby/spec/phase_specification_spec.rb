@@ -30,4 +30,24 @@ RSpec.describe 'specifying which phases to include' do
 
     expect(n).to_not eq(10)
   end
+
+  it 'does not generate when generation is skipped' do
+    expect do
+      hypothesis(phases: Phase.excluding(:generate), database: false) do
+        raise 'This should never run'
+      end
+    end.to_not raise_exception
+  end
+
+  it 'replays the database when generation is skipped' do
+    expect do
+      hypothesis { expect(any(integers)).to be < 10 }
+    end.to raise_exception(RSpec::Expectations::ExpectationNotMetError)
+
+    expect do
+      hypothesis(phases: Phase.excluding(:generate)) do
+        expect(any(integers)).to be < 10
+      end
+    end.to raise_exception(RSpec::Expectations::ExpectationNotMetError)
+  end
 end
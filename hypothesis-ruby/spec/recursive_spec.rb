@@ -0,0 +1,36 @@
+# frozen_string_literal: true
+
+RSpec.describe 'recursive possible' do
+  include Hypothesis::Debug
+
+  def depth(value)
+    return 0 unless value.is_a?(Array)
+
+    1 + (value.map { |v| depth(v) }.max || 0)
+  end
+
+  def nested_arrays(**kwargs)
+    recursive(integers(min: 0, max: 10), **kwargs) do |children|
+      arrays(of: children, min_size: 1, max_size: 3)
+    end
+  end
+
+  it 'provides leaves' do
+    find_any { any(nested_arrays).is_a?(Integer) }
+  end
+
+  it 'provides nested branches' do
+    find_any { depth(any(nested_arrays)) >= 2 }
+  end
+
+  it 'respects the maximum depth' do
+    hypothesis do
+      expect(depth(any(nested_arrays(max_depth: 2)))).to be <= 2
+    end
+  end
+
+  it 'shrinks towards leaves' do
+    value, = find { depth(any(nested_arrays)) >= 2 }
+    expect(value).to eq([[0]])
+  end
+end
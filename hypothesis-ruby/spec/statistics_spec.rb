@@ -0,0 +1,53 @@
+# frozen_string_literal: true
+
+RSpec.describe 'engine statistics' do
+  it 'reports what happened in each phase' do
+    engine = Hypothesis::Engine.new(
+      'statistics', max_examples: 10, phases: Phase.all, database: false
+    )
+    Hypothesis::World.current_engine = engine
+    begin
+      engine.run { |test_case| test_case.any(integers) }
+    ensure
+      Hypothesis::World.current_engine = nil
+    end
+    statistics = engine.statistics
+    expect(statistics[:exit_reason]).to be(:max_examples)
+    expect(statistics[:phases][:generate][:valid]).to eq(10)
+  end
+
+  it 'reports attempts made by shrink passes' do
+    engine = Hypothesis::Engine.new(
+      'statistics', max_examples: 1000, phases: Phase.all, database: false
+    )
+    Hypothesis::World.current_engine = engine
+    begin
+      engine.run do |test_case|
+        raise 'Too big' if test_case.any(integers) > 100
+      end
+    rescue RuntimeError
+      nil
+    ensure
+      Hypothesis::World.current_engine = nil
+    end
+    statistics = engine.statistics
+    expect(statistics[:exit_reason]).to be(:complete)
+    expect(statistics[:shrink_passes]).not_to be_empty
+  end
+
+  it 'counts events' do
+    engine = Hypothesis::Engine.new(
+      'statistics', max_examples: 10, phases: Phase.all, database: false
+    )
+    Hypothesis::World.current_engine = engine
+    begin
+      engine.run do |test_case|
+        test_case.any(integers)
+        event 'ran'
+      end
+    ensure
+      Hypothesis::World.current_engine = nil
+    end
+    expect(engine.statistics[:phases][:generate][:events]).to eq('ran' => 10)
+  end
+end
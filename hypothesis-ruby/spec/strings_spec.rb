@@ -33,3 +33,75 @@ RSpec.describe 'strings' do
     end
   end
 end
+
+RSpec.describe 'strings' do
+  include Hypothesis::Debug
+
+  they 'shrink towards zero' do
+    s, = find { !any(strings).empty? }
+    expect(s).to eq('0')
+  end
+
+  they 'respect categories' do
+    hypothesis do
+      s = any(strings(max_codepoint: 127, categories: %i[uppercase number]))
+      expect(s).to match(/\A[A-Z0-9]*\z/)
+    end
+  end
+
+  they 'respect categories outside of ascii' do
+    hypothesis do
+      s = any(strings(min_codepoint: 128, categories: %i[number]))
+      expect(s.codepoints).to all(be >= 128)
+      expect(s).to_not match(/[\p{L}\p{P}\s]/)
+    end
+  end
+
+  they 'respect excluded characters' do
+    hypothesis do
+      s = any(strings(max_codepoint: 127, exclude_characters: 'abc'))
+      expect(s).to_not match(/[abc]/)
+    end
+  end
+
+  they 'can be built from custom codepoints' do
+    hypothesis do
+      s = any(strings(codepoints: codepoints(min: 97, max: 122)))
+      expect(s).to match(/\A[a-z]*\z/)
+    end
+  end
+
+  they 'reject unknown categories' do
+    expect do
+      hypothesis { any(strings(categories: [:nonsense])) }
+    end.to raise_exception(ArgumentError)
+  end
+
+  they 'reject empty alphabets' do
+    expect { strings(categories: []) }.to raise_exception(ArgumentError)
+    expect { codepoints(min: 0x110000) }.to raise_exception(ArgumentError)
+  end
+
+  they 'reject codepoint bounds that are not codepoints' do
+    expect { codepoints(max: 2**32 + 65) }.to raise_exception(ArgumentError)
+    expect { codepoints(min: -1) }.to raise_exception(ArgumentError)
+    expect { strings(max_codepoint: 2**64) }.to raise_exception(ArgumentError)
+  end
+end
+
+RSpec.describe 'binaries' do
+  include Hypothesis::Debug
+
+  they 'respect size bounds' do
+    hypothesis do
+      s = any(binaries(min_size: 2, max_size: 5))
+      expect(s.bytesize).to be_between(2, 5)
+      expect(s.encoding).to eq(Encoding::ASCII_8BIT)
+    end
+  end
+
+  they 'shrink towards zero bytes' do
+    s, = find { any(binaries).bytesize >= 2 }
+    expect(s).to eq("\0\0".b)
+  end
+end
@@ -0,0 +1,14 @@
+# frozen_string_literal: true
+
+RSpec.describe 'targeting' do
+  include Hypothesis::Debug
+
+  it 'finds test cases that generation alone is unlikely to' do
+    n = find(max_examples: 100) do
+      x = any integers(min: 0, max: 2**32 - 1)
+      target x
+      x >= 2**32 - 100
+    end
+    expect(n).to eq([2**32 - 100])
+  end
+end
@@ -8,15 +8,16 @@ use std::convert::TryFrom;
 use std::mem;
 
 use rutie::{
-    AnyException, AnyObject, Array, Boolean, Class, Exception, Float, Integer, NilClass, Object,
-    RString, Symbol, VM,
+    AnyException, AnyObject, Array, Boolean, Class, Encoding, Exception, Float, Hash, Integer,
+    NilClass, Object, RString, Symbol, VM,
 };
 
 use conjecture::data::{DataSource, Status, TestResult};
 use conjecture::database::{BoxedDatabase, DirectoryDatabase, NoDatabase};
 use conjecture::distributions;
-use conjecture::distributions::Repeat;
-use conjecture::engine::{Engine, Phase};
+use conjecture::distributions::{Alphabet, CharCategory, Repeat};
+use conjecture::engine::{Engine, HealthCheck, LoopExitReason, Phase, Settings};
+use conjecture::statistics::Statistics;
 
 pub struct HypothesisCoreDataSourceStruct {
     source: Option<DataSource>,
@@ -29,9 +30,9 @@ impl HypothesisCoreDataSourceStruct {
         }
     }
 
-    fn start_draw(&mut self) {
+    fn start_draw(&mut self, label: u64) {
         if let Some(ref mut source) = self.source {
-            source.start_draw();
+            source.start_draw(label);
         }
     }
 
@@ -40,6 +41,18 @@ impl HypothesisCoreDataSourceStruct {
             source.stop_draw();
         }
     }
+
+    fn target(&mut self, label: &str, score: f64) {
+        if let Some(ref mut source) = self.source {
+            source.target(label, score);
+        }
+    }
+
+    fn note_event(&mut self, event: &str) {
+        if let Some(ref mut source) = self.source {
+            source.note_event(event);
+        }
+    }
 }
 
 wrappable_struct!(
@@ -54,10 +67,10 @@ class!(HypothesisCoreDataSource);
 methods!(
     HypothesisCoreDataSource,
     itself,
-    fn ruby_hypothesis_core_data_source_start_draw() -> NilClass {
+    fn ruby_hypothesis_core_data_source_start_draw(label: Integer) -> NilClass {
         itself
             .get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER)
-            .start_draw();
+            .start_draw(safe_access(label).to_u64());
 
         NilClass::new()
     }
@@ -68,6 +81,20 @@ methods!(
 
         NilClass::new()
     }
+    fn ruby_hypothesis_core_data_source_target(label: RString, score: Float) -> NilClass {
+        itself
+            .get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER)
+            .target(safe_access(label).to_str(), safe_access(score).to_f64());
+
+        NilClass::new()
+    }
+    fn ruby_hypothesis_core_data_source_note_event(event: RString) -> NilClass {
+        itself
+            .get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER)
+            .note_event(safe_access(event).to_str());
+
+        NilClass::new()
+    }
 );
 
 pub struct HypothesisCoreEngineStruct {
@@ -83,6 +110,7 @@ impl HypothesisCoreEngineStruct {
         seed: u64,
         max_examples: u64,
         phases: Vec<Phase>,
+        suppress_health_check: Vec<HealthCheck>,
     ) -> HypothesisCoreEngineStruct {
         let xs: [u32; 2] = [seed as u32, (seed >> 32) as u32];
         let db: BoxedDatabase = match database_path {
@@ -90,8 +118,15 @@ impl HypothesisCoreEngineStruct {
             Some(path) => Box::new(DirectoryDatabase::new(path)),
         };
 
+        let settings = Settings {
+            max_examples,
+            phases,
+            suppress_health_check,
+            ..Settings::default()
+        };
+
         HypothesisCoreEngineStruct {
-            engine: Engine::new(name, max_examples, phases, &xs, db),
+            engine: Engine::new(name, settings, &xs, db),
             pending: None,
             interesting_examples: Vec::new(),
         }
@@ -125,6 +160,16 @@ impl HypothesisCoreEngineStruct {
         self.engine.was_unsatisfiable()
     }
 
+    fn statistics(&self) -> Option<Statistics> {
+        self.engine.statistics()
+    }
+
+    fn health_check_failure(&self) -> Option<String> {
+        self.engine
+            .health_check_failure()
+            .map(|check| check.to_string())
+    }
+
     fn finish_overflow(&mut self, child: &mut HypothesisCoreDataSourceStruct) {
         mark_child_status(&mut self.engine, child, Status::Overflow);
     }
@@ -159,7 +204,8 @@ methods!(
         database_path: RString,
         seed: Integer,
         max_example: Integer,
-        phases: Array
+        phases: Array,
+        suppress_health_check: Array
     ) -> AnyObject {
         let rust_phases = safe_access(phases)
             .into_iter()
@@ -172,12 +218,24 @@ methods!(
             })
             .collect();
 
+        let rust_suppress_health_check = safe_access(suppress_health_check)
+            .into_iter()
+            .map(|ruby_check| {
+                let check_sym = safe_access(ruby_check.try_convert_to::<Symbol>());
+                let check = HealthCheck::try_from(check_sym.to_str())
+                    .map_err(|e| AnyException::new("ArgumentError", Some(&e)));
+
+                safe_access(check)
+            })
+            .collect();
+
         let core_engine = HypothesisCoreEngineStruct::new(
             safe_access(name).to_string(),
             database_path.ok().map(|p| p.to_string()),
             safe_access(seed).to_u64(),
             safe_access(max_example).to_u64(),
             rust_phases,
+            rust_suppress_health_check,
         );
 
         Class::from_existing("HypothesisCoreEngine")
@@ -251,6 +309,22 @@ methods!(
 
         Boolean::new(core_engine.was_unsatisfiable())
     }
+    fn ruby_hypothesis_core_engine_statistics() -> AnyObject {
+        let core_engine = itself.get_data(&*HYPOTHESIS_CORE_ENGINE_STRUCT_WRAPPER);
+
+        match core_engine.statistics() {
+            Some(statistics) => statistics_to_hash(&statistics).into(),
+            None => NilClass::new().into(),
+        }
+    }
+    fn ruby_hypothesis_core_engine_health_check_failure() -> AnyObject {
+        let core_engine = itself.get_data(&*HYPOTHESIS_CORE_ENGINE_STRUCT_WRAPPER);
+
+        match core_engine.health_check_failure() {
+            Some(message) => RString::new_utf8(&message).into(),
+            None => NilClass::new().into(),
+        }
+    }
 );
 
 pub struct HypothesisCoreIntegersStruct {
@@ -378,6 +452,68 @@ methods!(
     }
 );
 
+pub struct HypothesisCoreRecursiveStruct {
+    recursive: distributions::Recursive,
+}
+
+impl HypothesisCoreRecursiveStruct {
+    fn start_node(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<bool> {
+        data.source
+            .as_mut()
+            .and_then(|ref mut source| self.recursive.start_node(source).ok())
+    }
+
+    fn stop_branch(&mut self, data: &mut HypothesisCoreDataSourceStruct) {
+        if let Some(ref mut source) = data.source {
+            self.recursive.stop_branch(source);
+        }
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreRecursiveStruct,
+    HypothesisCoreRecursiveStructWrapper,
+    HYPOTHESIS_CORE_RECURSIVE_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreRecursive);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreRecursive,
+    itself,
+    fn ruby_hypothesis_core_recursive_new(max_depth: Integer, max_nodes: Integer) -> AnyObject {
+        let recursive = HypothesisCoreRecursiveStruct {
+            recursive: distributions::Recursive::new(
+                safe_access(max_depth).to_u64(),
+                safe_access(max_nodes).to_u64(),
+            ),
+        };
+
+        Class::from_existing("HypothesisCoreRecursive")
+            .wrap_data(recursive, &*HYPOTHESIS_CORE_RECURSIVE_STRUCT_WRAPPER)
+    }
+    fn ruby_hypothesis_core_recursive_start_node(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let recursive = itself.get_data_mut(&*HYPOTHESIS_CORE_RECURSIVE_STRUCT_WRAPPER);
+
+        match recursive.start_node(data_source) {
+            Some(b) => Boolean::new(b).into(),
+            None => NilClass::new().into(),
+        }
+    }
+    fn ruby_hypothesis_core_recursive_stop_branch(data: AnyObject) -> NilClass {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let recursive = itself.get_data_mut(&*HYPOTHESIS_CORE_RECURSIVE_STRUCT_WRAPPER);
+
+        recursive.stop_branch(data_source);
+
+        NilClass::new()
+    }
+);
+
 pub struct HypothesisCoreBoundedIntegersStruct {
     max_value: u64,
 }
@@ -425,6 +561,352 @@ methods!(
     }
 );
 
+pub struct HypothesisCoreSamplerStruct {
+    sampler: distributions::Sampler,
+}
+
+impl HypothesisCoreSamplerStruct {
+    fn provide(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<usize> {
+        data.source
+            .as_mut()
+            .and_then(|ref mut source| self.sampler.sample(source).ok())
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreSamplerStruct,
+    HypothesisCoreSamplerStructWrapper,
+    HYPOTHESIS_CORE_SAMPLER_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreSampler);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreSampler,
+    itself,
+    fn ruby_hypothesis_core_sampler_new(weights: Array) -> AnyObject {
+        let weights: Vec<f32> = safe_access(weights)
+            .into_iter()
+            .map(|w| safe_access(w.try_convert_to::<Float>()).to_f64() as f32)
+            .collect();
+        let sampler = distributions::Sampler::new(&weights)
+            .map_err(|e| AnyException::new("ArgumentError", Some(&e.to_string())));
+        let sampler = HypothesisCoreSamplerStruct {
+            sampler: safe_access(sampler),
+        };
+
+        Class::from_existing("HypothesisCoreSampler")
+            .wrap_data(sampler, &*HYPOTHESIS_CORE_SAMPLER_STRUCT_WRAPPER)
+    }
+    fn ruby_hypothesis_core_sampler_provide(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let sampler = itself.get_data_mut(&*HYPOTHESIS_CORE_SAMPLER_STRUCT_WRAPPER);
+
+        match sampler.provide(data_source) {
+            Some(i) => Integer::from(i as u64).into(),
+            None => NilClass::new().into(),
+        }
+    }
+);
+
+pub struct HypothesisCoreIntegersInRangeStruct {
+    lo: i128,
+    hi: i128,
+}
+
+impl HypothesisCoreIntegersInRangeStruct {
+    fn provide(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<i128> {
+        data.source.as_mut().and_then(|ref mut source| {
+            distributions::integers_in_range(source, self.lo, self.hi).ok()
+        })
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreIntegersInRangeStruct,
+    HypothesisCoreIntegersInRangeStructWrapper,
+    HYPOTHESIS_CORE_INTEGERS_IN_RANGE_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreIntegersInRange);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreIntegersInRange,
+    itself,
+    fn ruby_hypothesis_core_integers_in_range_new(lo: Integer, hi: Integer) -> AnyObject {
+        let lo = integer_to_i128(safe_access(lo));
+        let hi = integer_to_i128(safe_access(hi));
+        let integers_in_range = if lo <= hi {
+            Ok(HypothesisCoreIntegersInRangeStruct { lo, hi })
+        } else {
+            Err(AnyException::new(
+                "ArgumentError",
+                Some("Cannot draw integers from an empty range"),
+            ))
+        };
+
+        Class::from_existing("HypothesisCoreIntegersInRange").wrap_data(
+            safe_access(integers_in_range),
+            &*HYPOTHESIS_CORE_INTEGERS_IN_RANGE_STRUCT_WRAPPER,
+        )
+    }
+    fn ruby_hypothesis_core_integers_in_range_provide(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let integers_in_range =
+            itself.get_data_mut(&*HYPOTHESIS_CORE_INTEGERS_IN_RANGE_STRUCT_WRAPPER);
+
+        match integers_in_range.provide(data_source) {
+            Some(i) => i128_to_integer(i),
+            None => NilClass::new().into(),
+        }
+    }
+);
+
+pub struct HypothesisCoreCharactersStruct {
+    characters: distributions::Characters,
+}
+
+impl HypothesisCoreCharactersStruct {
+    fn provide(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<char> {
+        data.source
+            .as_mut()
+            .and_then(|ref mut source| self.characters.draw(source).ok())
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreCharactersStruct,
+    HypothesisCoreCharactersStructWrapper,
+    HYPOTHESIS_CORE_CHARACTERS_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreCharacters);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreCharacters,
+    itself,
+    fn ruby_hypothesis_core_characters_new(
+        min_codepoint: Integer,
+        max_codepoint: Integer,
+        categories: Array,
+        exclude_categories: Array,
+        exclude_characters: RString
+    ) -> AnyObject {
+        let alphabet = alphabet_from_ruby(
+            safe_access(min_codepoint),
+            safe_access(max_codepoint),
+            categories.ok(),
+            safe_access(exclude_categories),
+            safe_access(exclude_characters),
+        );
+        let characters = distributions::Characters::new(&alphabet)
+            .map_err(|e| AnyException::new("ArgumentError", Some(&e.to_string())));
+        let characters = HypothesisCoreCharactersStruct {
+            characters: safe_access(characters),
+        };
+
+        Class::from_existing("HypothesisCoreCharacters")
+            .wrap_data(characters, &*HYPOTHESIS_CORE_CHARACTERS_STRUCT_WRAPPER)
+    }
+    fn ruby_hypothesis_core_characters_provide(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let characters = itself.get_data_mut(&*HYPOTHESIS_CORE_CHARACTERS_STRUCT_WRAPPER);
+
+        match characters.provide(data_source) {
+            Some(c) => Integer::from(c as u32).into(),
+            None => NilClass::new().into(),
+        }
+    }
+);
+
+pub struct HypothesisCoreTextStruct {
+    characters: distributions::Characters,
+    min_size: u64,
+    max_size: u64,
+}
+
+impl HypothesisCoreTextStruct {
+    fn provide(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<String> {
+        data.source.as_mut().and_then(|ref mut source| {
+            distributions::text(source, &self.characters, self.min_size, self.max_size).ok()
+        })
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreTextStruct,
+    HypothesisCoreTextStructWrapper,
+    HYPOTHESIS_CORE_TEXT_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreText);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreText,
+    itself,
+    fn ruby_hypothesis_core_text_new(
+        min_codepoint: Integer,
+        max_codepoint: Integer,
+        categories: Array,
+        exclude_categories: Array,
+        exclude_characters: RString,
+        min_size: Integer,
+        max_size: Integer
+    ) -> AnyObject {
+        let alphabet = alphabet_from_ruby(
+            safe_access(min_codepoint),
+            safe_access(max_codepoint),
+            categories.ok(),
+            safe_access(exclude_categories),
+            safe_access(exclude_characters),
+        );
+        let characters = distributions::Characters::new(&alphabet)
+            .map_err(|e| AnyException::new("ArgumentError", Some(&e.to_string())));
+        let text = HypothesisCoreTextStruct {
+            characters: safe_access(characters),
+            min_size: safe_access(min_size).to_u64(),
+            max_size: safe_access(max_size).to_u64(),
+        };
+
+        Class::from_existing("HypothesisCoreText")
+            .wrap_data(text, &*HYPOTHESIS_CORE_TEXT_STRUCT_WRAPPER)
+    }
+    fn ruby_hypothesis_core_text_provide(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let text = itself.get_data_mut(&*HYPOTHESIS_CORE_TEXT_STRUCT_WRAPPER);
+
+        match text.provide(data_source) {
+            Some(s) => RString::new_utf8(&s).into(),
+            None => NilClass::new().into(),
+        }
+    }
+);
+
+pub struct HypothesisCoreBinaryStruct {
+    min_size: u64,
+    max_size: u64,
+}
+
+impl HypothesisCoreBinaryStruct {
+    fn provide(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<Vec<u8>> {
+        data.source.as_mut().and_then(|ref mut source| {
+            distributions::binary(source, self.min_size, self.max_size).ok()
+        })
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreBinaryStruct,
+    HypothesisCoreBinaryStructWrapper,
+    HYPOTHESIS_CORE_BINARY_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreBinary);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreBinary,
+    itself,
+    fn ruby_hypothesis_core_binary_new(min_size: Integer, max_size: Integer) -> AnyObject {
+        let binary = HypothesisCoreBinaryStruct {
+            min_size: safe_access(min_size).to_u64(),
+            max_size: safe_access(max_size).to_u64(),
+        };
+
+        Class::from_existing("HypothesisCoreBinary")
+            .wrap_data(binary, &*HYPOTHESIS_CORE_BINARY_STRUCT_WRAPPER)
+    }
+    fn ruby_hypothesis_core_binary_provide(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let binary = itself.get_data_mut(&*HYPOTHESIS_CORE_BINARY_STRUCT_WRAPPER);
+
+        match binary.provide(data_source) {
+            Some(bytes) => {
+                let encoding = safe_access(Encoding::find("ASCII-8BIT"));
+                RString::from_bytes(&bytes, &encoding).into()
+            }
+            None => NilClass::new().into(),
+        }
+    }
+);
+
+pub struct HypothesisCoreFloatsStruct {
+    min_value: f64,
+    max_value: f64,
+    allow_nan: bool,
+    allow_infinity: bool,
+}
+
+impl HypothesisCoreFloatsStruct {
+    fn provide(&mut self, data: &mut HypothesisCoreDataSourceStruct) -> Option<f64> {
+        data.source.as_mut().and_then(|ref mut source| {
+            distributions::floats(
+                source,
+                self.min_value,
+                self.max_value,
+                self.allow_nan,
+                self.allow_infinity,
+            )
+            .ok()
+        })
+    }
+}
+
+wrappable_struct!(
+    HypothesisCoreFloatsStruct,
+    HypothesisCoreFloatsStructWrapper,
+    HYPOTHESIS_CORE_FLOATS_STRUCT_WRAPPER
+);
+
+class!(HypothesisCoreFloats);
+
+#[rustfmt::skip]
+methods!(
+    HypothesisCoreFloats,
+    itself,
+    fn ruby_hypothesis_core_floats_new(
+        min_value: Float,
+        max_value: Float,
+        allow_nan: Boolean,
+        allow_infinity: Boolean
+    ) -> AnyObject {
+        let floats = HypothesisCoreFloatsStruct {
+            min_value: safe_access(min_value).to_f64(),
+            max_value: safe_access(max_value).to_f64(),
+            allow_nan: safe_access(allow_nan).to_bool(),
+            allow_infinity: safe_access(allow_infinity).to_bool(),
+        };
+        let bounds =
+            distributions::float_bounds(floats.min_value, floats.max_value, floats.allow_infinity)
+                .ok_or_else(|| {
+                    AnyException::new("ArgumentError", Some("Cannot draw floats from an empty range"))
+                });
+        safe_access(bounds);
+
+        Class::from_existing("HypothesisCoreFloats")
+            .wrap_data(floats, &*HYPOTHESIS_CORE_FLOATS_STRUCT_WRAPPER)
+    }
+    fn ruby_hypothesis_core_floats_provide(data: AnyObject) -> AnyObject {
+        let mut rdata = safe_access(data);
+        let data_source = rdata.get_data_mut(&*HYPOTHESIS_CORE_DATA_SOURCE_STRUCT_WRAPPER);
+        let floats = itself.get_data_mut(&*HYPOTHESIS_CORE_FLOATS_STRUCT_WRAPPER);
+
+        match floats.provide(data_source) {
+            Some(f) => Float::new(f).into(),
+            None => NilClass::new().into(),
+        }
+    }
+);
+
 #[allow(non_snake_case)]
 #[no_mangle]
 pub extern "C" fn Init_rutie_hypothesis_core() {
@@ -440,6 +922,11 @@ pub extern "C" fn Init_rutie_hypothesis_core() {
             "was_unsatisfiable",
             ruby_hypothesis_core_engine_was_unsatisfiable,
         );
+        klass.def("statistics", ruby_hypothesis_core_engine_statistics);
+        klass.def(
+            "health_check_failure",
+            ruby_hypothesis_core_engine_health_check_failure,
+        );
         klass.def(
             "finish_overflow",
             ruby_hypothesis_core_engine_finish_overflow,
@@ -455,6 +942,8 @@ pub extern "C" fn Init_rutie_hypothesis_core() {
     Class::new("HypothesisCoreDataSource", None).define(|klass| {
         klass.def("start_draw", ruby_hypothesis_core_data_source_start_draw);
         klass.def("stop_draw", ruby_hypothesis_core_data_source_stop_draw);
+        klass.def("note_event", ruby_hypothesis_core_data_source_note_event);
+        klass.def("target", ruby_hypothesis_core_data_source_target);
     });
 
     Class::new("HypothesisCoreIntegers", None).define(|klass| {
@@ -475,6 +964,42 @@ pub extern "C" fn Init_rutie_hypothesis_core() {
         klass.def_self("new", ruby_hypothesis_core_bounded_integers_new);
         klass.def("provide", ruby_hypothesis_core_bounded_integers_provide);
     });
+
+    Class::new("HypothesisCoreRecursive", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_recursive_new);
+        klass.def("_start_node", ruby_hypothesis_core_recursive_start_node);
+        klass.def("_stop_branch", ruby_hypothesis_core_recursive_stop_branch);
+    });
+
+    Class::new("HypothesisCoreSampler", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_sampler_new);
+        klass.def("provide", ruby_hypothesis_core_sampler_provide);
+    });
+
+    Class::new("HypothesisCoreIntegersInRange", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_integers_in_range_new);
+        klass.def("provide", ruby_hypothesis_core_integers_in_range_provide);
+    });
+
+    Class::new("HypothesisCoreCharacters", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_characters_new);
+        klass.def("provide", ruby_hypothesis_core_characters_provide);
+    });
+
+    Class::new("HypothesisCoreText", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_text_new);
+        klass.def("provide", ruby_hypothesis_core_text_provide);
+    });
+
+    Class::new("HypothesisCoreBinary", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_binary_new);
+        klass.def("provide", ruby_hypothesis_core_binary_provide);
+    });
+
+    Class::new("HypothesisCoreFloats", None).define(|klass| {
+        klass.def_self("new", ruby_hypothesis_core_floats_new);
+        klass.def("provide", ruby_hypothesis_core_floats_provide);
+    });
 }
 
 fn mark_child_status(
@@ -487,6 +1012,129 @@ fn mark_child_status(
     }
 }
 
+fn statistics_to_hash(statistics: &Statistics) -> Hash {
+    let mut phases = Hash::new();
+    for (phase, phase_statistics) in &statistics.phases {
+        let mut hash = Hash::new();
+        hash.store(Symbol::new("valid"), Integer::from(phase_statistics.valid));
+        hash.store(
+            Symbol::new("invalid"),
+            Integer::from(phase_statistics.invalid),
+        );
+        hash.store(
+            Symbol::new("overflow"),
+            Integer::from(phase_statistics.overflow),
+        );
+        hash.store(
+            Symbol::new("interesting"),
+            Integer::from(phase_statistics.interesting),
+        );
+        hash.store(
+            Symbol::new("duration"),
+            Float::new(phase_statistics.duration.as_secs_f64()),
+        );
+        let mut events = Hash::new();
+        for (event, count) in &phase_statistics.events {
+            events.store(RString::new_utf8(event), Integer::from(*count));
+        }
+        hash.store(Symbol::new("events"), events);
+        let mut draws_by_label = Hash::new();
+        for (label, count) in &phase_statistics.draws_by_label {
+            draws_by_label.store(Integer::from(*label), Integer::from(*count));
+        }
+        hash.store(Symbol::new("draws_by_label"), draws_by_label);
+        phases.store(Symbol::new(&phase.to_string()), hash);
+    }
+
+    let mut shrink_passes = Hash::new();
+    for (pass, pass_statistics) in &statistics.shrink_passes {
+        let mut hash = Hash::new();
+        hash.store(
+            Symbol::new("attempts"),
+            Integer::from(pass_statistics.attempts),
+        );
+        hash.store(
+            Symbol::new("successes"),
+            Integer::from(pass_statistics.successes),
+        );
+        shrink_passes.store(Symbol::new(&pass.to_string()), hash);
+    }
+
+    let exit_reason: AnyObject = match statistics.exit_reason {
+        None => NilClass::new().into(),
+        Some(ref reason) => Symbol::new(match reason {
+            LoopExitReason::Complete => "complete",
+            LoopExitReason::MaxExamples => "max_examples",
+            LoopExitReason::Exhausted => "exhausted",
+            LoopExitReason::ShrinkLimitReached => "shrink_limit_reached",
+            LoopExitReason::HealthCheckFailed(_) => "health_check_failed",
+        })
+        .to_any_object(),
+    };
+
+    let mut result = Hash::new();
+    result.store(Symbol::new("phases"), phases);
+    result.store(Symbol::new("shrink_passes"), shrink_passes);
+    result.store(Symbol::new("exit_reason"), exit_reason);
+    result
+}
+
+fn categories_from_ruby(categories: Array) -> Vec<CharCategory> {
+    categories
+        .into_iter()
+        .map(|ruby_category| {
+            let category_sym = safe_access(ruby_category.try_convert_to::<Symbol>());
+            let category = CharCategory::try_from(category_sym.to_str())
+                .map_err(|e| AnyException::new("ArgumentError", Some(&e)));
+
+            safe_access(category)
+        })
+        .collect()
+}
+
+fn alphabet_from_ruby(
+    min_codepoint: Integer,
+    max_codepoint: Integer,
+    categories: Option<Array>,
+    exclude_categories: Array,
+    exclude_characters: RString,
+) -> Alphabet {
+    Alphabet {
+        min_codepoint: codepoint_from_ruby(min_codepoint),
+        max_codepoint: codepoint_from_ruby(max_codepoint),
+        categories: categories.map(categories_from_ruby),
+        exclude_categories: categories_from_ruby(exclude_categories),
+        exclude_characters: exclude_characters.to_string().chars().collect(),
+    }
+}
+
+// Ruby integers can be arbitrarily large, so we convert them by way
+// of their decimal representation.
+fn integer_to_i128(value: Integer) -> i128 {
+    let decimal =
+        safe_access(safe_access(value.protect_send("to_s", &[])).try_convert_to::<RString>());
+    safe_access(decimal.to_str().parse().map_err(|_| {
+        AnyException::new("ArgumentError", Some("Integer bounds must fit in 128 bits"))
+    }))
+}
+
+fn codepoint_from_ruby(value: Integer) -> u32 {
+    let codepoint = u32::try_from(integer_to_i128(value))
+        .ok()
+        .filter(|&c| c <= char::MAX as u32)
+        .ok_or_else(|| {
+            AnyException::new(
+                "ArgumentError",
+                Some("Codepoints must be between 0 and 0x10FFFF"),
+            )
+        });
+    safe_access(codepoint)
+}
+
+fn i128_to_integer(value: i128) -> AnyObject {
+    safe_access(RString::new_utf8(&value.to_string()).protect_send("to_i", &[]))
+}
+
 fn safe_access<T>(value: Result<T, AnyException>) -> T {
     value.map_err(VM::raise_ex).unwrap()
 }